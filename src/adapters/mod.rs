@@ -1,6 +1,7 @@
 use crate::Address;
 
 pub mod paged;
+pub mod partition;
 pub mod ram;
 pub mod spi;
 
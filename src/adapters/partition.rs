@@ -0,0 +1,73 @@
+use core::cell::RefCell;
+
+use crate::adapters::*;
+
+#[derive(Debug, PartialEq)]
+pub enum Error<E> {
+    AdapterError(E),
+    AdapterBusy,
+    OutOfBounds,
+}
+
+pub struct PartitionAdapter<'a, A>
+where
+    A: StoreAdapter,
+{
+    inner: &'a RefCell<A>,
+    offset: Address,
+    size: usize,
+}
+
+impl<'a, A> PartitionAdapter<'a, A>
+where
+    A: StoreAdapter,
+{
+    pub fn new(inner: &'a RefCell<A>, offset: Address, size: usize) -> Self {
+        assert!(offset + size <= inner.borrow().max_address());
+        Self {
+            inner,
+            offset,
+            size,
+        }
+    }
+
+    pub fn offset(&self) -> Address {
+        self.offset
+    }
+
+    fn check_bounds(&self, addr: Address, len: usize) -> Result<Address, Error<A::Error>> {
+        if addr + len > self.size {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(self.offset + addr)
+    }
+}
+
+impl<'a, A> StoreAdapter for PartitionAdapter<'a, A>
+where
+    A: StoreAdapter,
+{
+    type Error = Error<A::Error>;
+
+    fn read(&mut self, addr: Address, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let addr = self.check_bounds(addr, buf.len())?;
+        self.inner
+            .try_borrow_mut()
+            .map_err(|_| Error::AdapterBusy)?
+            .read(addr, buf)
+            .map_err(Error::AdapterError)
+    }
+
+    fn write(&mut self, addr: Address, data: &[u8]) -> Result<(), Self::Error> {
+        let addr = self.check_bounds(addr, data.len())?;
+        self.inner
+            .try_borrow_mut()
+            .map_err(|_| Error::AdapterBusy)?
+            .write(addr, data)
+            .map_err(Error::AdapterError)
+    }
+
+    fn max_address(&self) -> Address {
+        self.size
+    }
+}
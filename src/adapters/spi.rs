@@ -145,7 +145,7 @@ impl<SPI: spi::Transfer<u8> + spi::Write<u8>, CS: OutputPin, const ADDR_BYTES: u
 
     fn read(&mut self, addr: Address, buf: &mut [u8]) -> Result<(), Self::Error> {
         let addr = addr + self.cfg.offset;
-        assert!(!buf.is_empty() && addr + buf.len() < self.cfg.max_addr);
+        assert!(!buf.is_empty() && addr + buf.len() <= self.cfg.max_addr);
 
         self.transaction(|spi| {
             let mut cmd_buf = Self::mem_cmd(Command::Read, addr);
@@ -159,7 +159,7 @@ impl<SPI: spi::Transfer<u8> + spi::Write<u8>, CS: OutputPin, const ADDR_BYTES: u
 
     fn write(&mut self, addr: Address, data: &[u8]) -> Result<(), Self::Error> {
         let addr = addr + self.cfg.offset;
-        assert!(!data.is_empty() && addr + data.len() < self.cfg.max_addr);
+        assert!(!data.is_empty() && addr + data.len() <= self.cfg.max_addr);
 
         self.transaction(|spi| {
             spi.write(&[Command::WriteEnable as u8])
@@ -2,7 +2,7 @@ use crate::*;
 use core::cmp::{Eq, Ord, Ordering, PartialEq};
 
 #[derive(Debug, Default, Copy, Clone)]
-pub(crate) struct Slot {
+pub struct Slot {
     start: Address,
     end: Address,
 }
@@ -45,27 +45,83 @@ pub enum AllocStrategy {
     FirstFit,
 }
 
-pub struct Alloc<const SLOTS: usize> {
-    pub(crate) slots: [Slot; SLOTS],
+pub trait Allocator {
+    fn reset(&mut self, start: Address, space: usize);
+    fn capacity(&self) -> usize;
+    fn alloc(&mut self, size: usize, addr: Option<Address>) -> Option<Address>;
+    fn extend(&mut self, addr: Address, size: usize) -> Option<Address> {
+        self.alloc(size, Some(addr))
+    }
+    fn free(&mut self, addr: Address, size: usize);
+    fn free_space(&self) -> usize;
+    fn largest_slot(&self) -> usize;
+    fn slots_used(&self) -> usize;
+    fn has_untracked_space(&self) -> bool;
+}
+
+pub struct Alloc<S> {
+    pub(crate) slots: S,
     alloc_strategy: AllocStrategy,
+    untracked: bool,
 }
 
-impl<const SLOTS: usize> Alloc<SLOTS> {
-    pub fn new(alloc_strategy: AllocStrategy, start: Address, space: usize) -> Self {
-        let mut slots = [Slot::default(); SLOTS];
-        if SLOTS > 0 {
-            slots[0] = Slot::new(start, space + start);
-        }
-        Self {
+impl<S> Alloc<S>
+where
+    S: AsRef<[Slot]> + AsMut<[Slot]>,
+{
+    pub fn new(slots: S, alloc_strategy: AllocStrategy, start: Address, space: usize) -> Self {
+        let mut res = Self {
             alloc_strategy,
             slots,
+            untracked: false,
+        };
+        res.reset(start, space);
+        res
+    }
+
+    fn track(&mut self, start: Address, end: Address) {
+        let range = Slot::new(start, end);
+        if range.size() == 0 {
+            return;
+        }
+
+        match self.slots.as_mut().iter_mut().min() {
+            Some(slot) if slot.size() == 0 => *slot = range,
+            Some(slot) if slot.size() < range.size() => {
+                *slot = range;
+                self.untracked = true;
+            }
+            _ => self.untracked = true,
         }
     }
+}
 
-    pub fn alloc(&mut self, size: usize, addr: Option<Address>) -> Option<Address> {
+impl<S> Allocator for Alloc<S>
+where
+    S: AsRef<[Slot]> + AsMut<[Slot]>,
+{
+    fn reset(&mut self, start: Address, space: usize) {
+        let slots = self.slots.as_mut();
+        slots.fill(Slot::default());
+        if let Some(slot) = slots.first_mut() {
+            *slot = Slot::new(start, space + start);
+        }
+        self.untracked = false;
+    }
+
+    fn has_untracked_space(&self) -> bool {
+        self.untracked
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.as_ref().len()
+    }
+
+    fn alloc(&mut self, size: usize, addr: Option<Address>) -> Option<Address> {
         if let Some(addr) = addr {
             match self
                 .slots
+                .as_mut()
                 .iter_mut()
                 .find(|s| addr >= s.start && addr < s.end && s.size() - (addr - s.start) >= size)
             {
@@ -74,23 +130,32 @@ impl<const SLOTS: usize> Alloc<SLOTS> {
                     Some(addr)
                 }
                 Some(slot) => {
-                    let slot_end = slot.end;
-                    slot.end = addr;
-                    if let Some(unused_slot) = self.slots.iter_mut().find(|s| s.size() == 0) {
-                        unused_slot.start = addr + size;
-                        unused_slot.end = slot_end;
-                    } else {
-                        return None;
-                    };
+                    let head = Slot::new(slot.start, addr);
+                    let tail = Slot::new(addr + size, slot.end);
+                    *slot = Slot::max(head, tail);
+                    let rest = Slot::min(head, tail);
+                    self.track(rest.start, rest.end);
                     Some(addr)
                 }
                 _ => None,
             }
         } else {
             let slot = match self.alloc_strategy {
-                AllocStrategy::MaxFit => self.slots.iter_mut().filter(|s| s.size() >= size).max(),
-                AllocStrategy::MinFit => self.slots.iter_mut().filter(|s| s.size() >= size).min(),
-                AllocStrategy::FirstFit => self.slots.iter_mut().find(|s| s.size() >= size),
+                AllocStrategy::MaxFit => self
+                    .slots
+                    .as_mut()
+                    .iter_mut()
+                    .filter(|s| s.size() >= size)
+                    .max(),
+                AllocStrategy::MinFit => self
+                    .slots
+                    .as_mut()
+                    .iter_mut()
+                    .filter(|s| s.size() >= size)
+                    .min(),
+                AllocStrategy::FirstFit => {
+                    self.slots.as_mut().iter_mut().find(|s| s.size() >= size)
+                }
             }?;
             let start = slot.start;
             slot.start += size;
@@ -98,15 +163,42 @@ impl<const SLOTS: usize> Alloc<SLOTS> {
         }
     }
 
-    pub fn free(&mut self, addr: Address, size: usize) {
+    fn free_space(&self) -> usize {
+        self.slots.as_ref().iter().map(Slot::size).sum()
+    }
+
+    fn largest_slot(&self) -> usize {
+        self.slots
+            .as_ref()
+            .iter()
+            .map(Slot::size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn slots_used(&self) -> usize {
+        self.slots.as_ref().iter().filter(|s| s.size() > 0).count()
+    }
+
+    fn free(&mut self, addr: Address, size: usize) {
+        if size == 0 {
+            return;
+        }
+
         let slot_end = addr + size;
-        if let Some(slot) = self.slots.iter_mut().find(|s| s.end == addr) {
-            slot.end += size;
-        } else if let Some(slot) = self.slots.iter_mut().find(|s| s.start == slot_end) {
-            slot.start = addr;
-        } else if let Some(slot) = self.slots.iter_mut().find(|s| s.size() == 0) {
-            slot.start = addr;
-            slot.end = slot_end;
+        let slots = self.slots.as_mut();
+        let left = slots.iter().position(|s| s.size() > 0 && s.end == addr);
+        let right = slots
+            .iter()
+            .position(|s| s.size() > 0 && s.start == slot_end);
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                slots[left].end = slots[right].end;
+                slots[right] = Slot::default();
+            }
+            (Some(left), None) => slots[left].end = slot_end,
+            (None, Some(right)) => slots[right].start = addr,
+            (None, None) => self.track(addr, slot_end),
         }
     }
 }
@@ -36,10 +36,10 @@ fn main() {
 fn pack(keys: &Vec<String>, nonce: u16) -> usize {
     let mut buckets = HashMap::new();
     for key in keys {
-        let hopper = Grasshopper::<{ BUCKETS }>::new(1_000, nonce, key.as_bytes());
+        let hopper = Grasshopper::new(BUCKETS, 1_000, nonce, key.as_bytes());
         for (hop, bucket) in hopper.enumerate() {
-            if buckets.get(&bucket).is_none() {
-                buckets.insert(bucket, hop + 1);
+            if let std::collections::hash_map::Entry::Vacant(entry) = buckets.entry(bucket) {
+                entry.insert(hop + 1);
                 break;
             }
         }
@@ -0,0 +1,205 @@
+use crate::*;
+
+const WORD_BITS: usize = u32::BITS as usize;
+
+pub struct BlockAlloc<M> {
+    pub(crate) bitmap: M,
+    alloc_strategy: AllocStrategy,
+    min_block_size: usize,
+    block_size: usize,
+    start: Address,
+    space: usize,
+    blocks: usize,
+    used: usize,
+    runs: usize,
+}
+
+impl<M> BlockAlloc<M>
+where
+    M: AsRef<[u32]> + AsMut<[u32]>,
+{
+    pub fn new(
+        bitmap: M,
+        block_size: usize,
+        alloc_strategy: AllocStrategy,
+        start: Address,
+        space: usize,
+    ) -> Self {
+        let mut res = Self {
+            bitmap,
+            alloc_strategy,
+            min_block_size: usize::max(block_size, 1),
+            block_size: 0,
+            start,
+            space: 0,
+            blocks: 0,
+            used: 0,
+            runs: 0,
+        };
+        res.reset(start, space);
+        res
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    pub fn used_blocks(&self) -> usize {
+        self.used
+    }
+
+    fn is_used(&self, block: usize) -> bool {
+        self.bitmap.as_ref()[block / WORD_BITS] & (1 << (block % WORD_BITS)) != 0
+    }
+
+    fn is_free(&self, block: Option<usize>) -> bool {
+        matches!(block, Some(block) if block < self.blocks && !self.is_used(block))
+    }
+
+    fn mark(&mut self, block: usize, used: bool) {
+        if self.is_used(block) == used {
+            return;
+        }
+
+        let left = self.is_free(block.checked_sub(1));
+        let right = self.is_free(Some(block + 1));
+        let word = &mut self.bitmap.as_mut()[block / WORD_BITS];
+        *word ^= 1 << (block % WORD_BITS);
+
+        match (left, right, used) {
+            (true, true, true) | (false, false, false) => self.runs += 1,
+            (true, true, false) | (false, false, true) => self.runs -= 1,
+            _ => {}
+        }
+        if used {
+            self.used += 1;
+        } else {
+            self.used -= 1;
+        }
+    }
+
+    fn claim(&mut self, size: usize, addr: Address, head_owned: bool) -> Option<Address> {
+        if addr < self.start || addr + size > self.start + self.space {
+            return None;
+        }
+
+        let offset = addr - self.start;
+        let head = (!offset.is_multiple_of(self.block_size)).then_some(offset / self.block_size);
+        let first = offset.div_ceil(self.block_size);
+        let last = (offset + size).div_ceil(self.block_size);
+        if (first..last).any(|block| self.is_used(block))
+            || (!head_owned && head.is_some_and(|block| self.is_used(block)))
+        {
+            return None;
+        }
+        head.into_iter()
+            .chain(first..last)
+            .for_each(|block| self.mark(block, true));
+        Some(addr)
+    }
+
+    fn run_bytes(&self, start: usize, end: usize) -> usize {
+        usize::min(end * self.block_size, self.space) - start * self.block_size
+    }
+
+    fn free_runs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut block = 0;
+        core::iter::from_fn(move || {
+            while block < self.blocks && self.is_used(block) {
+                block += 1;
+            }
+            if block == self.blocks {
+                return None;
+            }
+            let start = block;
+            while block < self.blocks && !self.is_used(block) {
+                block += 1;
+            }
+            Some((start, block))
+        })
+    }
+}
+
+impl<M> Allocator for BlockAlloc<M>
+where
+    M: AsRef<[u32]> + AsMut<[u32]>,
+{
+    fn reset(&mut self, start: Address, space: usize) {
+        self.bitmap.as_mut().fill(0);
+        let bits = self.capacity();
+        self.start = start;
+        self.space = space;
+        self.block_size = usize::max(self.min_block_size, space.div_ceil(usize::max(bits, 1)));
+        self.blocks = usize::min(space.div_ceil(self.block_size), bits);
+        self.used = 0;
+        self.runs = usize::from(self.blocks > 0);
+    }
+
+    fn capacity(&self) -> usize {
+        self.bitmap.as_ref().len() * WORD_BITS
+    }
+
+    fn alloc(&mut self, size: usize, addr: Option<Address>) -> Option<Address> {
+        if let Some(addr) = addr {
+            return self.claim(size, addr, false);
+        }
+
+        let count = size.div_ceil(self.block_size);
+        let found = {
+            let mut runs = self.free_runs().filter(|(start, end)| {
+                end - start >= count && self.run_bytes(*start, *end) >= size
+            });
+            match self.alloc_strategy {
+                AllocStrategy::MaxFit => runs.max_by_key(|(start, end)| end - start),
+                AllocStrategy::MinFit => runs.min_by_key(|(start, end)| end - start),
+                AllocStrategy::FirstFit => runs.next(),
+            }
+        };
+        let (start, _) = found?;
+
+        (start..start + count).for_each(|block| self.mark(block, true));
+        Some(self.start + start * self.block_size)
+    }
+
+    fn extend(&mut self, addr: Address, size: usize) -> Option<Address> {
+        self.claim(size, addr, true)
+    }
+
+    fn free(&mut self, addr: Address, size: usize) {
+        if addr < self.start {
+            return;
+        }
+
+        let offset = addr - self.start;
+        let first = offset.div_ceil(self.block_size);
+        let last = usize::min((offset + size).div_ceil(self.block_size), self.blocks);
+        (first..last).for_each(|block| self.mark(block, false));
+    }
+
+    fn free_space(&self) -> usize {
+        let mut free = (self.blocks - self.used) * self.block_size;
+        if self.is_free(self.blocks.checked_sub(1)) {
+            free -= self.blocks * self.block_size - self.space;
+        }
+        free
+    }
+
+    fn largest_slot(&self) -> usize {
+        self.free_runs()
+            .map(|(start, end)| self.run_bytes(start, end))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn slots_used(&self) -> usize {
+        self.runs
+    }
+
+    fn has_untracked_space(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,276 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::adapters::*;
+use crate::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CodecError {
+    BufferOverflow,
+    Unsupported,
+    Malformed,
+    TrailingBytes,
+}
+
+pub trait Codec {
+    fn encode<T: Serialize + ?Sized, E>(&self, val: &T, buf: &mut [u8]) -> Result<usize, Error<E>>;
+
+    fn decode<'de, T: Deserialize<'de>, E>(&self, bytes: &'de mut [u8]) -> Result<T, Error<E>>;
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Postcard;
+
+impl Codec for Postcard {
+    fn encode<T: Serialize + ?Sized, E>(&self, val: &T, buf: &mut [u8]) -> Result<usize, Error<E>> {
+        let res = postcard::to_slice(val, buf).map_err(Error::SerializationError)?;
+        Ok(res.len())
+    }
+
+    fn decode<'de, T: Deserialize<'de>, E>(&self, bytes: &'de mut [u8]) -> Result<T, Error<E>> {
+        postcard::from_bytes(bytes).map_err(Error::SerializationError)
+    }
+}
+
+#[cfg(feature = "cbor")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    fn encode<T: Serialize + ?Sized, E>(&self, val: &T, buf: &mut [u8]) -> Result<usize, Error<E>> {
+        let mut ser = minicbor_serde::Serializer::new(minicbor::encode::write::Cursor::new(buf));
+        val.serialize(&mut ser)
+            .map_err(|err| match err.as_write() {
+                Some(_) => Error::CodecError(CodecError::BufferOverflow),
+                None => Error::CodecError(CodecError::Unsupported),
+            })?;
+        Ok(ser.encoder().writer().position())
+    }
+
+    fn decode<'de, T: Deserialize<'de>, E>(&self, bytes: &'de mut [u8]) -> Result<T, Error<E>> {
+        let mut de = minicbor_serde::Deserializer::new(bytes);
+        let val = T::deserialize(&mut de).map_err(|_| Error::CodecError(CodecError::Malformed))?;
+        if de.decoder().position() != de.decoder().input().len() {
+            return Err(Error::CodecError(CodecError::TrailingBytes));
+        }
+        Ok(val)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MessagePack {
+    named: bool,
+}
+
+#[cfg(feature = "msgpack")]
+impl MessagePack {
+    pub fn named(self) -> Self {
+        let mut res = self;
+        res.named = true;
+        res
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePack {
+    fn encode<T: Serialize + ?Sized, E>(&self, val: &T, buf: &mut [u8]) -> Result<usize, Error<E>> {
+        let capacity = buf.len();
+        let mut cursor = buf;
+        let res = match self.named {
+            true => rmp_serde::encode::write_named(&mut cursor, val),
+            false => rmp_serde::encode::write(&mut cursor, val),
+        };
+        res.map_err(|err| match err {
+            rmp_serde::encode::Error::InvalidValueWrite(_) => {
+                Error::CodecError(CodecError::BufferOverflow)
+            }
+            _ => Error::CodecError(CodecError::Unsupported),
+        })?;
+        Ok(capacity - cursor.len())
+    }
+
+    fn decode<'de, T: Deserialize<'de>, E>(&self, bytes: &'de mut [u8]) -> Result<T, Error<E>> {
+        rmp_serde::from_slice(bytes).map_err(|_| Error::CodecError(CodecError::Malformed))
+    }
+}
+
+pub struct Encoded<'a, A, C, K>
+where
+    A: StoreAdapter,
+    C: Capacity,
+    K: Codec,
+{
+    store: &'a mut Store<A, C>,
+    codec: &'a K,
+}
+
+impl<'a, E, A, C, K> Encoded<'a, A, C, K>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+    K: Codec,
+{
+    pub fn new(store: &'a mut Store<A, C>, codec: &'a K) -> Self {
+        Self { store, codec }
+    }
+
+    pub fn insert_val<T: Serialize, const N: usize>(
+        &mut self,
+        id: &[u8],
+        val: &T,
+    ) -> Result<Bucket, Error<E>> {
+        self.store.insert_val_with::<_, T, N>(self.codec, id, val)
+    }
+
+    pub fn load_val<T: DeserializeOwned, const N: usize>(
+        &mut self,
+        id: &[u8],
+    ) -> Result<T, Error<E>> {
+        self.store.load_val_with::<_, T, N>(self.codec, id)
+    }
+
+    pub fn load_val_in<'b, T: Deserialize<'b>>(
+        &mut self,
+        id: &[u8],
+        buf: &'b mut [u8],
+    ) -> Result<T, Error<E>> {
+        self.store.load_val_in_with(self.codec, id, buf)
+    }
+}
+
+pub(crate) struct ValueReader<'a, A: StoreAdapter> {
+    adapter: &'a mut A,
+    addr: Address,
+    remaining: usize,
+    scratch: &'a mut [u8],
+    error: Option<A::Error>,
+}
+
+impl<'a, A: StoreAdapter> ValueReader<'a, A> {
+    pub(crate) fn new(adapter: &'a mut A, bucket: &Bucket, scratch: &'a mut [u8]) -> Self {
+        Self {
+            adapter,
+            addr: bucket.val_address(),
+            remaining: bucket.val_len(),
+            scratch,
+            error: None,
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> postcard::Result<()> {
+        if buf.len() > self.remaining {
+            return Err(postcard::Error::DeserializeUnexpectedEnd);
+        }
+        if let Err(err) = self.adapter.read(self.addr, buf) {
+            self.error = Some(err);
+            return Err(postcard::Error::DeserializeUnexpectedEnd);
+        }
+        self.addr += buf.len();
+        self.remaining -= buf.len();
+        Ok(())
+    }
+}
+
+impl<'a, A: StoreAdapter + 'a> postcard::de_flavors::Flavor<'a> for ValueReader<'a, A> {
+    type Remainder = Option<A::Error>;
+    type Source = A;
+
+    fn pop(&mut self) -> postcard::Result<u8> {
+        let mut byte = [0];
+        self.read(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+
+    fn try_take_n(&mut self, ct: usize) -> postcard::Result<&'a [u8]> {
+        if ct > self.scratch.len() {
+            return Err(postcard::Error::DeserializeUnexpectedEnd);
+        }
+        let (head, tail) = core::mem::take(&mut self.scratch).split_at_mut(ct);
+        self.scratch = tail;
+        self.read(head)?;
+        Ok(head)
+    }
+
+    fn try_take_n_temp<'b>(&'b mut self, ct: usize) -> postcard::Result<&'b [u8]>
+    where
+        'a: 'b,
+    {
+        if ct > self.scratch.len() {
+            return Err(postcard::Error::DeserializeUnexpectedEnd);
+        }
+        let scratch = core::mem::take(&mut self.scratch);
+        let res = self.read(&mut scratch[..ct]);
+        self.scratch = scratch;
+        res?;
+        Ok(&self.scratch[..ct])
+    }
+
+    fn finalize(self) -> postcard::Result<Option<A::Error>> {
+        Ok(self.error)
+    }
+}
+
+pub(crate) struct ValueWriter<'a, A: StoreAdapter> {
+    adapter: &'a mut A,
+    addr: Address,
+    remaining: usize,
+    chunk: [u8; 32],
+    len: usize,
+    error: &'a mut Option<A::Error>,
+}
+
+impl<'a, A: StoreAdapter> ValueWriter<'a, A> {
+    pub(crate) fn new(
+        adapter: &'a mut A,
+        bucket: &Bucket,
+        error: &'a mut Option<A::Error>,
+    ) -> Self {
+        Self {
+            adapter,
+            addr: bucket.val_address(),
+            remaining: bucket.val_len(),
+            chunk: [0; 32],
+            len: 0,
+            error,
+        }
+    }
+
+    fn flush(&mut self) -> postcard::Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        if let Err(err) = self.adapter.write(self.addr, &self.chunk[..self.len]) {
+            *self.error = Some(err);
+            return Err(postcard::Error::SerializeBufferFull);
+        }
+        self.addr += self.len;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl<A: StoreAdapter> postcard::ser_flavors::Flavor for ValueWriter<'_, A> {
+    type Output = ();
+
+    fn try_push(&mut self, data: u8) -> postcard::Result<()> {
+        if self.remaining == 0 {
+            return Err(postcard::Error::SerializeBufferFull);
+        }
+        self.chunk[self.len] = data;
+        self.len += 1;
+        self.remaining -= 1;
+        if self.len == self.chunk.len() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> postcard::Result<()> {
+        self.flush()
+    }
+}
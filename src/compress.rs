@@ -0,0 +1,303 @@
+use crate::adapters::*;
+use crate::*;
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const COMPRESSION_HEADER_LEN: usize = 5;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = MIN_MATCH + 0x7f;
+const MAX_LITERALS: usize = 0x80;
+const WINDOW_SIZE: usize = 256;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Compression {
+    None,
+    #[default]
+    Lzss,
+}
+
+impl Compression {
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Lzss),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lzss => 1,
+        }
+    }
+}
+
+pub struct Compressed<'a, A, C>
+where
+    A: StoreAdapter,
+    C: Capacity,
+{
+    store: &'a mut Store<A, C>,
+    buf: &'a mut [u8],
+    compression: Compression,
+}
+
+impl<'a, E, A, C> Compressed<'a, A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub fn new(store: &'a mut Store<A, C>, buf: &'a mut [u8]) -> Self {
+        Self {
+            store,
+            buf,
+            compression: Compression::default(),
+        }
+    }
+
+    pub fn compression(self, compression: Compression) -> Self {
+        let mut res = self;
+        res.compression = compression;
+        res
+    }
+
+    pub fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        if val.len() > u32::MAX as usize {
+            return Err(Error::ValueOverflow);
+        }
+
+        if self.compression == Compression::Lzss {
+            let packed = match self.buf.get_mut(COMPRESSION_HEADER_LEN..) {
+                Some(out) => lzss_compress(val, out),
+                None => None,
+            };
+            if let Some(packed) = packed.filter(|packed| *packed < val.len()) {
+                self.buf[..COMPRESSION_HEADER_LEN]
+                    .copy_from_slice(&record_header(Compression::Lzss, val.len()));
+                let len = COMPRESSION_HEADER_LEN + packed;
+                let bucket = self.store.alloc_compressed(key, len)?;
+                self.store
+                    .adapter()
+                    .write(bucket.val_address(), &self.buf[..len])
+                    .map_err(Error::AdapterError)?;
+                return Ok(bucket);
+            }
+        }
+
+        let bucket = self
+            .store
+            .alloc_compressed(key, COMPRESSION_HEADER_LEN + val.len())?;
+        let addr = bucket.val_address();
+        let adapter = self.store.adapter();
+        adapter
+            .write(addr, &record_header(Compression::None, val.len()))
+            .map_err(Error::AdapterError)?;
+        adapter
+            .write(addr + COMPRESSION_HEADER_LEN, val)
+            .map_err(Error::AdapterError)?;
+        Ok(bucket)
+    }
+
+    pub fn codec(&mut self, key: &[u8]) -> Result<Compression, Error<E>> {
+        let (compression, _, _) = self.load_header(key)?;
+        Ok(compression)
+    }
+
+    pub fn val_len(&mut self, key: &[u8]) -> Result<usize, Error<E>> {
+        let (_, _, val_len) = self.load_header(key)?;
+        Ok(val_len)
+    }
+
+    pub fn load_slice<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b [u8], Error<E>> {
+        let (compression, bucket, val_len) = self.load_header(key)?;
+        if val_len > buf.len() {
+            return Err(Error::ValueOverflow);
+        }
+
+        let header_len = if bucket.is_compressed() {
+            COMPRESSION_HEADER_LEN
+        } else {
+            0
+        };
+        let addr = bucket.val_address() + header_len;
+        let packed = bucket.val_len() - header_len;
+        match compression {
+            Compression::None => {
+                if packed != val_len {
+                    return Err(Error::CorruptedValue);
+                }
+                self.store
+                    .adapter()
+                    .read(addr, &mut buf[..val_len])
+                    .map_err(Error::AdapterError)?;
+            }
+            Compression::Lzss => {
+                if packed > self.buf.len() {
+                    return Err(Error::ValueOverflow);
+                }
+                self.store
+                    .adapter()
+                    .read(addr, &mut self.buf[..packed])
+                    .map_err(Error::AdapterError)?;
+                match lzss_decompress(&self.buf[..packed], &mut buf[..val_len]) {
+                    Some(len) if len == val_len => {}
+                    _ => return Err(Error::CorruptedValue),
+                }
+            }
+        }
+        Ok(&buf[..val_len])
+    }
+
+    pub fn load_str<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b str, Error<E>> {
+        let slice = self.load_slice(key, buf)?;
+        core::str::from_utf8(slice).map_err(Error::Utf8Error)
+    }
+
+    fn load_header(&mut self, key: &[u8]) -> Result<(Compression, Bucket, usize), Error<E>> {
+        let bucket = self.store.lookup(key)?;
+        if !bucket.is_compressed() {
+            let val_len = bucket.val_len();
+            return Ok((Compression::None, bucket, val_len));
+        }
+        if bucket.val_len() < COMPRESSION_HEADER_LEN {
+            return Err(Error::CorruptedValue);
+        }
+
+        let mut header = [0; COMPRESSION_HEADER_LEN];
+        self.store
+            .adapter()
+            .read(bucket.val_address(), &mut header)
+            .map_err(Error::AdapterError)?;
+        let (compression, val_len) = parse_header(&header);
+        Ok((compression.ok_or(Error::CorruptedValue)?, bucket, val_len))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E, A, C> Compressed<'_, A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub fn insert_val<T: Serialize, const N: usize>(
+        &mut self,
+        id: &[u8],
+        val: &T,
+    ) -> Result<Bucket, crate::Error<E>> {
+        let mut buf = [0; N];
+        let len = Postcard.encode(val, &mut buf)?;
+        self.insert(id, &buf[..len])
+    }
+
+    pub fn load_val<T: DeserializeOwned, const N: usize>(
+        &mut self,
+        id: &[u8],
+    ) -> Result<T, crate::Error<E>> {
+        let mut buf = [0; N];
+        let len = self.load_slice(id, &mut buf)?.len();
+        Postcard.decode(&mut buf[..len])
+    }
+}
+
+fn record_header(compression: Compression, val_len: usize) -> [u8; COMPRESSION_HEADER_LEN] {
+    let mut header = [0; COMPRESSION_HEADER_LEN];
+    header[0] = compression.id();
+    header[1..].copy_from_slice(&(val_len as u32).to_be_bytes());
+    header
+}
+
+fn parse_header(header: &[u8; COMPRESSION_HEADER_LEN]) -> (Option<Compression>, usize) {
+    let mut val_len = [0; 4];
+    val_len.copy_from_slice(&header[1..]);
+    (
+        Compression::from_id(header[0]),
+        u32::from_be_bytes(val_len) as usize,
+    )
+}
+
+pub(crate) fn lzss_compress(input: &[u8], out: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let mut pos = 0;
+    let mut literals = 0;
+
+    while pos < input.len() {
+        let (offset, run) = longest_match(input, pos);
+        if run < MIN_MATCH {
+            pos += 1;
+            continue;
+        }
+
+        len = push_literals(out, len, &input[literals..pos])?;
+        let token = out.get_mut(len..len + 2)?;
+        token[0] = 0x80 | (run - MIN_MATCH) as u8;
+        token[1] = (offset - 1) as u8;
+        len += 2;
+        pos += run;
+        literals = pos;
+    }
+
+    push_literals(out, len, &input[literals..])
+}
+
+pub(crate) fn lzss_decompress(input: &[u8], out: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let token = input[pos];
+        pos += 1;
+
+        if token & 0x80 == 0 {
+            let count = token as usize + 1;
+            let literals = input.get(pos..pos + count)?;
+            out.get_mut(len..len + count)?.copy_from_slice(literals);
+            pos += count;
+            len += count;
+            continue;
+        }
+
+        let run = (token & 0x7f) as usize + MIN_MATCH;
+        let offset = *input.get(pos)? as usize + 1;
+        pos += 1;
+        if offset > len || len + run > out.len() {
+            return None;
+        }
+        for _ in 0..run {
+            out[len] = out[len - offset];
+            len += 1;
+        }
+    }
+
+    Some(len)
+}
+
+fn longest_match(input: &[u8], pos: usize) -> (usize, usize) {
+    let max = usize::min(MAX_MATCH, input.len() - pos);
+    let mut best = (0, 0);
+    for start in pos.saturating_sub(WINDOW_SIZE)..pos {
+        let run = (0..max)
+            .take_while(|idx| input[start + idx] == input[pos + idx])
+            .count();
+        if run > best.1 {
+            best = (pos - start, run);
+            if run == max {
+                break;
+            }
+        }
+    }
+    best
+}
+
+fn push_literals(out: &mut [u8], len: usize, literals: &[u8]) -> Option<usize> {
+    let mut len = len;
+    for chunk in literals.chunks(MAX_LITERALS) {
+        let token = out.get_mut(len..len + 1 + chunk.len())?;
+        token[0] = (chunk.len() - 1) as u8;
+        token[1..].copy_from_slice(chunk);
+        len += token.len();
+    }
+    Some(len)
+}
@@ -0,0 +1,367 @@
+use core::hash::Hasher;
+
+use siphasher::sip128::{Hasher128, SipHasher24};
+
+use crate::adapters::*;
+use crate::*;
+
+pub const KEY_ID_LEN: usize = 16;
+
+const NONCE_COUNTER_LEN: usize = 8;
+const NONCE_RESERVE: u64 = 64;
+const MAX_NONCE_LEN: usize = 32;
+const MAX_TAG_LEN: usize = 32;
+const KEY_HASH_CONTEXT: &[u8] = b"kvs/key-hash";
+
+#[derive(Debug, PartialEq)]
+pub struct CipherError;
+
+pub trait Cipher {
+    const NONCE_LEN: usize;
+    const TAG_LEN: usize;
+
+    fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), CipherError>;
+
+    fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), CipherError>;
+}
+
+#[cfg(feature = "aead")]
+impl<T: aead::AeadInPlace> Cipher for T {
+    const NONCE_LEN: usize = <T::NonceSize as aead::generic_array::typenum::Unsigned>::USIZE;
+    const TAG_LEN: usize = <T::TagSize as aead::generic_array::typenum::Unsigned>::USIZE;
+
+    fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), CipherError> {
+        let res = self
+            .encrypt_in_place_detached(aead::Nonce::<T>::from_slice(nonce), aad, buf)
+            .map_err(|_| CipherError)?;
+        tag.copy_from_slice(&res);
+        Ok(())
+    }
+
+    fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), CipherError> {
+        self.decrypt_in_place_detached(
+            aead::Nonce::<T>::from_slice(nonce),
+            aad,
+            buf,
+            aead::Tag::<T>::from_slice(tag),
+        )
+        .map_err(|_| CipherError)
+    }
+}
+
+impl<E, A, C> Store<A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub(crate) fn load_nonce_counter(&mut self) -> Result<u64, Error<E>> {
+        if !self.nonce_slot {
+            self.reserve_nonce_slot()?;
+        }
+        let mut counter = [0; NONCE_COUNTER_LEN];
+        self.adapter
+            .read(self.nonce_slot_address(), &mut counter)
+            .map_err(Error::AdapterError)?;
+        Ok(u64::from_be_bytes(counter))
+    }
+
+    pub(crate) fn store_nonce_counter(&mut self, counter: u64) -> Result<(), Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+        self.adapter
+            .write(self.nonce_slot_address(), &counter.to_be_bytes())
+            .map_err(Error::AdapterError)
+    }
+
+    fn nonce_slot_address(&self) -> Address {
+        self.data_start() + self.data_size
+    }
+
+    fn reserve_nonce_slot(&mut self) -> Result<(), Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+
+        let data_size = self
+            .data_size
+            .checked_sub(NONCE_COUNTER_LEN)
+            .ok_or(Error::StoreOverflow)?;
+        let addr = self.data_start() + data_size;
+        self.alloc_space(NONCE_COUNTER_LEN, Some(addr), None)?
+            .ok_or(Error::StoreOverflow)?;
+        self.adapter
+            .write(addr, &[0; NONCE_COUNTER_LEN])
+            .map_err(Error::AdapterError)?;
+
+        self.data_size = data_size;
+        self.nonce_slot = true;
+        self.alloc_loaded = false;
+        self.write_header()
+    }
+}
+
+pub struct Encrypted<'a, A, C, K>
+where
+    A: StoreAdapter,
+    C: Capacity,
+    K: Cipher,
+{
+    store: &'a mut Store<A, C>,
+    cipher: &'a K,
+    buf: &'a mut [u8],
+    hide_keys: bool,
+    key_hash: Option<[u8; 16]>,
+    nonce: u64,
+    nonce_limit: Option<u64>,
+}
+
+impl<'a, E, A, C, K> Encrypted<'a, A, C, K>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+    K: Cipher,
+{
+    pub fn new(store: &'a mut Store<A, C>, cipher: &'a K, buf: &'a mut [u8]) -> Self {
+        const {
+            assert!(K::NONCE_LEN >= NONCE_COUNTER_LEN && K::NONCE_LEN <= MAX_NONCE_LEN);
+            assert!(K::TAG_LEN <= MAX_TAG_LEN);
+        }
+        Self {
+            store,
+            cipher,
+            buf,
+            hide_keys: false,
+            key_hash: None,
+            nonce: 0,
+            nonce_limit: None,
+        }
+    }
+
+    pub fn hide_keys(self) -> Self {
+        let mut res = self;
+        res.hide_keys = true;
+        res
+    }
+
+    pub fn overhead(&self) -> usize {
+        K::NONCE_LEN + K::TAG_LEN
+    }
+
+    pub fn max_value_len(&self) -> usize {
+        self.buf.len().saturating_sub(self.overhead())
+    }
+
+    pub fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        if val.len() > self.max_value_len() {
+            return Err(Error::ValueOverflow);
+        }
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.wiped(|enc| {
+            enc.buf[K::NONCE_LEN..K::NONCE_LEN + val.len()].copy_from_slice(val);
+            enc.seal(key, val.len())
+        })
+    }
+
+    pub fn append(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.wiped(|enc| {
+            let len = enc.open(key)?;
+            if len + val.len() > enc.max_value_len() {
+                return Err(Error::ValueOverflow);
+            }
+            let start = K::NONCE_LEN + len;
+            enc.buf[start..start + val.len()].copy_from_slice(val);
+            enc.seal(key, len + val.len())
+        })
+    }
+
+    pub fn patch(&mut self, key: &[u8], offset: usize, patch: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.wiped(|enc| {
+            let len = enc.open(key)?;
+            if offset > len {
+                return Err(Error::InvalidPatchOffset);
+            }
+            let new_len = usize::max(len, offset + patch.len());
+            if new_len > enc.max_value_len() {
+                return Err(Error::ValueOverflow);
+            }
+            let start = K::NONCE_LEN + offset;
+            enc.buf[start..start + patch.len()].copy_from_slice(patch);
+            enc.seal(key, new_len)
+        })
+    }
+
+    pub fn load_slice<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b [u8], Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        let len = self.wiped(|enc| {
+            let len = enc.open(key)?;
+            if len > buf.len() {
+                return Err(Error::ValueOverflow);
+            }
+            buf[..len].copy_from_slice(&enc.buf[K::NONCE_LEN..K::NONCE_LEN + len]);
+            Ok(len)
+        })?;
+        Ok(&buf[..len])
+    }
+
+    pub fn load_str<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b str, Error<E>> {
+        let slice = self.load_slice(key, buf)?;
+        core::str::from_utf8(slice).map_err(Error::Utf8Error)
+    }
+
+    pub fn exists(&mut self, key: &[u8]) -> Result<bool, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.store.exists(key)
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Result<(), Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.store.remove(key)
+    }
+
+    pub fn erase(&mut self, key: &[u8], fill_with: u8) -> Result<(), Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = self.stored_key(&mut scratch, key)?;
+        self.store.erase(key, fill_with)
+    }
+
+    fn stored_key<'k>(
+        &mut self,
+        scratch: &'k mut [u8; MAX_KEY_LEN],
+        key: &'k [u8],
+    ) -> Result<&'k [u8], Error<E>> {
+        if key.len() > MAX_KEY_LEN {
+            return Err(Error::KeyOverflow);
+        }
+        if key.is_empty() {
+            return Err(Error::InvalidKey);
+        }
+        if !self.hide_keys {
+            return Ok(key);
+        }
+
+        let subkey = match self.key_hash {
+            Some(subkey) => subkey,
+            None => {
+                let mut subkey = [0; 16];
+                let mut tag = [0; MAX_TAG_LEN];
+                let nonce = [0xff; MAX_NONCE_LEN];
+                self.cipher
+                    .encrypt(
+                        &nonce[..K::NONCE_LEN],
+                        KEY_HASH_CONTEXT,
+                        &mut subkey,
+                        &mut tag[..K::TAG_LEN],
+                    )
+                    .map_err(|_| Error::AuthenticationFailed)?;
+                self.key_hash = Some(subkey);
+                subkey
+            }
+        };
+
+        let mut hasher = SipHasher24::new_with_key(&subkey);
+        hasher.write(key);
+        scratch[..KEY_ID_LEN].copy_from_slice(&hasher.finish128().as_bytes());
+        Ok(&scratch[..KEY_ID_LEN])
+    }
+
+    fn wiped<T>(
+        &mut self,
+        op: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        let res = op(self);
+        self.buf.fill(0);
+        res
+    }
+
+    fn next_nonce(&mut self) -> Result<u64, Error<E>> {
+        let limit = match self.nonce_limit {
+            Some(limit) => limit,
+            None => {
+                self.nonce = self.store.load_nonce_counter()?;
+                self.nonce
+            }
+        };
+
+        if self.nonce == limit {
+            let limit = limit
+                .checked_add(NONCE_RESERVE)
+                .ok_or(Error::InvalidNonce)?;
+            self.store.store_nonce_counter(limit)?;
+            self.nonce_limit = Some(limit);
+        }
+
+        let nonce = self.nonce;
+        self.nonce += 1;
+        Ok(nonce)
+    }
+
+    fn seal(&mut self, key: &[u8], len: usize) -> Result<Bucket, Error<E>> {
+        let counter = self.next_nonce()?;
+        let (nonce, rest) = self.buf.split_at_mut(K::NONCE_LEN);
+        let (data, rest) = rest.split_at_mut(len);
+        nonce.fill(0);
+        nonce[K::NONCE_LEN - NONCE_COUNTER_LEN..].copy_from_slice(&counter.to_be_bytes());
+        self.cipher
+            .encrypt(nonce, key, data, &mut rest[..K::TAG_LEN])
+            .map_err(|_| Error::AuthenticationFailed)?;
+
+        let record_len = K::NONCE_LEN + len + K::TAG_LEN;
+        self.store.insert(key, &self.buf[..record_len])
+    }
+
+    fn open(&mut self, key: &[u8]) -> Result<usize, Error<E>> {
+        let bucket = self.store.lookup(key)?;
+        let val_len = bucket.val_len();
+        if val_len < self.overhead() {
+            return Err(Error::AuthenticationFailed);
+        }
+        if val_len > self.buf.len() {
+            return Err(Error::ValueOverflow);
+        }
+        self.store
+            .adapter()
+            .read(bucket.val_address(), &mut self.buf[..val_len])
+            .map_err(Error::AdapterError)?;
+
+        let len = val_len - self.overhead();
+        let (nonce, rest) = self.buf[..val_len].split_at_mut(K::NONCE_LEN);
+        let (data, tag) = rest.split_at_mut(len);
+        self.cipher
+            .decrypt(nonce, key, data, tag)
+            .map_err(|_| Error::AuthenticationFailed)?;
+        Ok(len)
+    }
+}
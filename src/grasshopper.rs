@@ -1,42 +1,46 @@
+use crate::*;
 use byteorder::{BigEndian, ByteOrder};
-use core::hash::Hasher;
-use hash32::Murmur3Hasher;
 
 #[derive(Default)]
-pub struct Grasshopper<const SIZE: usize> {
+pub struct Grasshopper<H = Murmur3> {
+    hasher: H,
+    buckets: usize,
     hops: usize,
-    hash: u16,
+    tag: u64,
     key_len: u32,
     token: u32,
 }
 
-impl<const SIZE: usize> Grasshopper<SIZE> {
-    pub fn new(hops: usize, nonce: u16, key: &[u8]) -> Self {
-        let mut hasher = Murmur3Hasher::default();
-
-        if nonce != 0 {
-            let mut nonce_buf = [0; 2];
-            BigEndian::write_u16(&mut nonce_buf, nonce);
-            hasher.write(&nonce_buf);
-        }
+impl Grasshopper<Murmur3> {
+    pub fn new(buckets: usize, hops: usize, nonce: u16, key: &[u8]) -> Self {
+        Self::with_hasher(Murmur3, buckets, hops, nonce, key)
+    }
+}
 
-        hasher.write(key);
-        let token = hash32::Hasher::finish32(&hasher);
+impl<H: KeyHasher> Grasshopper<H> {
+    pub fn with_hasher(hasher: H, buckets: usize, hops: usize, nonce: u16, key: &[u8]) -> Self {
+        let tag = hasher.hash(nonce, &[key]);
 
         Self {
+            hasher,
+            buckets,
             hops,
-            token,
-            hash: token as u16,
+            tag,
+            token: tag as u32,
             key_len: key.len() as u32,
         }
     }
 
     pub fn hash(&self) -> u16 {
-        self.hash
+        self.tag as u16
+    }
+
+    pub fn tag(&self) -> u64 {
+        self.tag
     }
 }
 
-impl<const SIZE: usize> Iterator for Grasshopper<SIZE> {
+impl<H: KeyHasher> Iterator for Grasshopper<H> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -44,20 +48,18 @@ impl<const SIZE: usize> Iterator for Grasshopper<SIZE> {
             return None;
         }
 
-        let mut hasher = Murmur3Hasher::default();
         let mut buf = [0, 0, 0, 0, self.key_len as u8];
         BigEndian::write_u32(&mut buf, self.token);
-        hasher.write(&buf);
 
-        self.token = hash32::Hasher::finish32(&hasher);
+        self.token = self.hasher.hash(0, &[&buf]) as u32;
         self.hops -= 1;
 
-        Some(self.token as usize % SIZE)
+        Some(self.token as usize % self.buckets)
     }
 }
 
-impl<const SIZE: usize> ExactSizeIterator for Grasshopper<SIZE> {
+impl<H: KeyHasher> ExactSizeIterator for Grasshopper<H> {
     fn len(&self) -> usize {
-        SIZE
+        self.buckets
     }
 }
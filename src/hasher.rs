@@ -0,0 +1,92 @@
+use byteorder::{BigEndian, ByteOrder};
+use core::hash::Hasher;
+use hash32::{FnvHasher, Murmur3Hasher};
+use siphasher::sip::SipHasher24;
+
+pub trait KeyHasher {
+    fn hash(&self, nonce: u16, parts: &[&[u8]]) -> u64;
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Murmur3;
+
+impl KeyHasher for Murmur3 {
+    fn hash(&self, nonce: u16, parts: &[&[u8]]) -> u64 {
+        let mut hasher = Murmur3Hasher::default();
+        write_parts(&mut hasher, nonce, parts);
+        hash32::Hasher::finish32(&hasher) as u64
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Fnv1a;
+
+impl KeyHasher for Fnv1a {
+    fn hash(&self, nonce: u16, parts: &[&[u8]]) -> u64 {
+        let mut hasher = FnvHasher::default();
+        write_parts(&mut hasher, nonce, parts);
+        hash32::Hasher::finish32(&hasher) as u64
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SipHash;
+
+impl KeyHasher for SipHash {
+    fn hash(&self, nonce: u16, parts: &[&[u8]]) -> u64 {
+        let mut hasher = SipHasher24::new_with_keys(nonce as u64, 0x6b76_7300_0000_0000);
+        for part in parts {
+            hasher.write(part);
+        }
+        hasher.finish()
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum HashAlgorithm {
+    #[default]
+    Murmur3,
+    Fnv1a,
+    SipHash,
+}
+
+impl HashAlgorithm {
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(HashAlgorithm::Murmur3),
+            1 => Some(HashAlgorithm::Fnv1a),
+            2 => Some(HashAlgorithm::SipHash),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            HashAlgorithm::Murmur3 => 0,
+            HashAlgorithm::Fnv1a => 1,
+            HashAlgorithm::SipHash => 2,
+        }
+    }
+}
+
+impl KeyHasher for HashAlgorithm {
+    fn hash(&self, nonce: u16, parts: &[&[u8]]) -> u64 {
+        match self {
+            HashAlgorithm::Murmur3 => Murmur3.hash(nonce, parts),
+            HashAlgorithm::Fnv1a => Fnv1a.hash(nonce, parts),
+            HashAlgorithm::SipHash => SipHash.hash(nonce, parts),
+        }
+    }
+}
+
+fn write_parts<H: Hasher>(hasher: &mut H, nonce: u16, parts: &[&[u8]]) {
+    if nonce != 0 {
+        let mut nonce_buf = [0; 2];
+        BigEndian::write_u16(&mut nonce_buf, nonce);
+        hasher.write(&nonce_buf);
+    }
+
+    for part in parts {
+        hasher.write(part);
+    }
+}
@@ -1,23 +1,57 @@
 #![no_std]
 
+#[cfg(feature = "msgpack")]
+extern crate std;
+
 use core::str::Utf8Error;
 
 use adapters::StoreAdapter;
 use modular_bitfield::prelude::*;
 
 mod alloc;
+mod bitmap;
+#[cfg(feature = "serde")]
+mod codec;
+mod compress;
+mod crypto;
 mod grasshopper;
+mod hasher;
+mod migrate;
+mod namespace;
+mod numeric;
+mod probe;
 mod store;
 
 pub mod adapters;
 
 pub use alloc::*;
+pub use bitmap::*;
+#[cfg(feature = "serde")]
+pub use codec::*;
+pub use compress::*;
+pub use crypto::*;
 pub use grasshopper::*;
+pub use hasher::*;
+pub use namespace::*;
+pub use probe::*;
 pub use store::*;
 
 pub const MAX_KEY_LEN: usize = 256;
 pub const MAX_VALUE_LEN: usize = 64 * 1024;
 
+pub const FORMAT_VERSION: u8 = 1;
+pub(crate) const FORMAT_SIGNATURE: u16 = 0x766b;
+pub(crate) const FLAG_WIDE_BUCKETS: u8 = 0x01;
+pub(crate) const FLAG_HASHER_MASK: u8 = 0x06;
+pub(crate) const FLAG_HASHER_SHIFT: u8 = 1;
+pub(crate) const FLAG_PROBE_MASK: u8 = 0x18;
+pub(crate) const FLAG_PROBE_SHIFT: u8 = 3;
+pub(crate) const FLAG_TOMBSTONES: u8 = 0x20;
+pub(crate) const FLAG_NONCE_SLOT: u8 = 0x40;
+pub(crate) const FLAG_RECORD_KINDS: u8 = 0x80;
+pub(crate) const TOMBSTONE_ADDRESS: u32 = 1;
+pub(crate) const MAX_BUCKET_SIZE: usize = 16;
+
 pub type Address = usize;
 
 #[derive(Debug)]
@@ -51,6 +85,10 @@ impl Bucket {
     pub fn record_len(&self) -> usize {
         self.key_len() + self.val_len()
     }
+
+    pub fn is_compressed(&self) -> bool {
+        self.raw.is_compressed()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -60,15 +98,27 @@ pub enum Error<E> {
     InvalidCapacity,
     InvalidNonce,
     InvalidPatchOffset,
+    InvalidValueLength,
+    KeyExists,
     KeyNotFound,
     ReadOnlyStore,
     StoreNotFound,
     StoreOverflow,
     ValueOverflow,
     KeyOverflow,
+    InvalidKey,
+    LegacyFormat,
+    QuotaExceeded,
+    UnsupportedVersion,
+    ValueMismatch,
+    AuthenticationFailed,
+    CorruptedValue,
+    CompressedValue,
     Utf8Error(Utf8Error),
     #[cfg(feature = "serde")]
     SerializationError(postcard::Error),
+    #[cfg(feature = "serde")]
+    CodecError(CodecError),
 }
 
 #[bitfield]
@@ -76,31 +126,267 @@ pub(crate) struct StoreHeader {
     magic: B32,
     nonce: B16,
     buckets: B16,
+    signature: B16,
+    version: B8,
+    flags: B8,
+    data_size: B32,
+}
+
+impl StoreHeader {
+    pub(crate) fn bucket_layout(&self) -> BucketLayout {
+        if self.flags() & FLAG_WIDE_BUCKETS != 0 {
+            BucketLayout::Wide
+        } else {
+            BucketLayout::Compact
+        }
+    }
+
+    pub(crate) fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        HashAlgorithm::from_id((self.flags() & FLAG_HASHER_MASK) >> FLAG_HASHER_SHIFT)
+    }
+
+    pub(crate) fn probe_strategy(&self) -> Option<ProbeStrategy> {
+        ProbeStrategy::from_id((self.flags() & FLAG_PROBE_MASK) >> FLAG_PROBE_SHIFT)
+    }
+
+    pub(crate) fn tombstones(&self) -> bool {
+        self.flags() & FLAG_TOMBSTONES != 0
+    }
+
+    pub(crate) fn nonce_slot(&self) -> bool {
+        self.flags() & FLAG_NONCE_SLOT != 0
+    }
+
+    pub(crate) fn record_kinds(&self) -> bool {
+        self.flags() & FLAG_RECORD_KINDS != 0
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum BucketLayout {
+    #[default]
+    Compact,
+    Wide,
+}
+
+impl BucketLayout {
+    pub fn bucket_size(self) -> usize {
+        match self {
+            BucketLayout::Compact => core::mem::size_of::<CompactBucket>(),
+            BucketLayout::Wide => core::mem::size_of::<WideBucket>(),
+        }
+    }
+
+    pub fn max_key_len(self) -> usize {
+        match self {
+            BucketLayout::Compact => u8::MAX as usize,
+            BucketLayout::Wide => MAX_KEY_LEN,
+        }
+    }
+
+    pub fn max_val_len(self) -> usize {
+        match self {
+            BucketLayout::Compact => u16::MAX as usize,
+            BucketLayout::Wide => MAX_VALUE_LEN,
+        }
+    }
+
+    pub fn max_store_size(self) -> usize {
+        match self {
+            BucketLayout::Compact => 1 << 24,
+            BucketLayout::Wide => u32::MAX as usize,
+        }
+    }
+
+    pub fn for_store_size(size: usize) -> Self {
+        if size > BucketLayout::Compact.max_store_size() {
+            BucketLayout::Wide
+        } else {
+            BucketLayout::Compact
+        }
+    }
+
+    pub(crate) fn tag_mask(self) -> u64 {
+        match self {
+            BucketLayout::Compact => u16::MAX as u64,
+            BucketLayout::Wide => (1 << 48) - 1,
+        }
+    }
+
+    pub(crate) fn kind_bit(self) -> u64 {
+        (self.tag_mask() >> 1) + 1
+    }
+
+    pub(crate) fn flags(self) -> u8 {
+        match self {
+            BucketLayout::Compact => 0,
+            BucketLayout::Wide => FLAG_WIDE_BUCKETS,
+        }
+    }
 }
 
 #[bitfield]
-#[derive(Default, Debug, Clone)]
-pub(crate) struct RawBucket {
+struct CompactBucket {
     val_len: B16,
     key_len: B8,
     address: B24,
     hash: B16,
 }
 
-pub struct KeysIterator<'a, 'b, A, const BUCKETS: usize, const SLOTS: usize>
+#[bitfield]
+struct WideBucket {
+    val_len: B32,
+    key_len: B16,
+    hash: B16,
+    address: B32,
+    hash_hi: B32,
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RawBucket {
+    val_len: u32,
+    key_len: u16,
+    address: u32,
+    hash: u64,
+    compressed: bool,
+}
+
+impl RawBucket {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn tombstone() -> Self {
+        Self {
+            address: TOMBSTONE_ADDRESS,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.key_len == 0 && self.address != TOMBSTONE_ADDRESS
+    }
+
+    pub(crate) fn from_bytes(layout: BucketLayout, record_kinds: bool, bytes: &[u8]) -> Self {
+        let mut res = match layout {
+            BucketLayout::Compact => {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(&bytes[..8]);
+                let raw = CompactBucket::from_bytes(buf);
+                Self {
+                    val_len: raw.val_len() as u32,
+                    key_len: raw.key_len() as u16,
+                    address: raw.address(),
+                    hash: raw.hash() as u64,
+                    compressed: false,
+                }
+            }
+            BucketLayout::Wide => {
+                let mut buf = [0; 16];
+                buf.copy_from_slice(&bytes[..16]);
+                let raw = WideBucket::from_bytes(buf);
+                Self {
+                    val_len: raw.val_len(),
+                    key_len: raw.key_len(),
+                    address: raw.address(),
+                    hash: raw.hash() as u64 | (raw.hash_hi() as u64) << 16,
+                    compressed: false,
+                }
+            }
+        };
+        if record_kinds {
+            res.compressed = res.hash & layout.kind_bit() != 0;
+            res.hash &= !layout.kind_bit();
+        }
+        res
+    }
+
+    pub(crate) fn into_bytes(self, layout: BucketLayout) -> [u8; MAX_BUCKET_SIZE] {
+        let mut buf = [0; MAX_BUCKET_SIZE];
+        let hash = if self.compressed {
+            self.hash | layout.kind_bit()
+        } else {
+            self.hash
+        };
+        match layout {
+            BucketLayout::Compact => {
+                let raw = CompactBucket::new()
+                    .with_val_len(self.val_len as u16)
+                    .with_key_len(self.key_len as u8)
+                    .with_address(self.address)
+                    .with_hash(hash as u16);
+                buf[..8].copy_from_slice(&raw.into_bytes());
+            }
+            BucketLayout::Wide => {
+                let raw = WideBucket::new()
+                    .with_val_len(self.val_len)
+                    .with_key_len(self.key_len)
+                    .with_address(self.address)
+                    .with_hash(hash as u16)
+                    .with_hash_hi((hash >> 16) as u32);
+                buf.copy_from_slice(&raw.into_bytes());
+            }
+        }
+        buf
+    }
+
+    pub(crate) fn val_len(&self) -> u32 {
+        self.val_len
+    }
+
+    pub(crate) fn set_val_len(&mut self, val_len: u32) {
+        self.val_len = val_len;
+    }
+
+    pub(crate) fn key_len(&self) -> u16 {
+        self.key_len
+    }
+
+    pub(crate) fn set_key_len(&mut self, key_len: u16) {
+        self.key_len = key_len;
+    }
+
+    pub(crate) fn address(&self) -> u32 {
+        self.address
+    }
+
+    pub(crate) fn set_address(&mut self, address: u32) {
+        self.address = address;
+    }
+
+    pub(crate) fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub(crate) fn set_hash(&mut self, hash: u64) {
+        self.hash = hash;
+    }
+
+    pub(crate) fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub(crate) fn set_compressed(&mut self, compressed: bool) {
+        self.compressed = compressed;
+    }
+}
+
+pub struct KeysIterator<'a, 'b, A, C>
 where
     A: StoreAdapter,
+    C: Capacity,
 {
-    store: &'a mut KVStore<A, BUCKETS, SLOTS>,
+    store: &'a mut Store<A, C>,
     prefix: Option<&'b [u8]>,
     cursor: usize,
 }
 
-impl<'a, 'b, A, const BUCKETS: usize, const SLOTS: usize> KeysIterator<'a, 'b, A, BUCKETS, SLOTS>
+impl<'a, 'b, A, C> KeysIterator<'a, 'b, A, C>
 where
     A: StoreAdapter,
+    C: Capacity,
 {
-    pub fn new(store: &'a mut KVStore<A, BUCKETS, SLOTS>) -> Self {
+    pub fn new(store: &'a mut Store<A, C>) -> Self {
         Self {
             store,
             cursor: 0,
@@ -108,7 +394,7 @@ where
         }
     }
 
-    pub fn with_prefix(store: &'a mut KVStore<A, BUCKETS, SLOTS>, prefix: &'b [u8]) -> Self {
+    pub fn with_prefix(store: &'a mut Store<A, C>, prefix: &'b [u8]) -> Self {
         Self {
             store,
             cursor: 0,
@@ -117,16 +403,16 @@ where
     }
 }
 
-impl<'a, 'b, A, const BUCKETS: usize, const SLOTS: usize> Iterator
-    for KeysIterator<'a, 'b, A, BUCKETS, SLOTS>
+impl<'a, 'b, A, C> Iterator for KeysIterator<'a, 'b, A, C>
 where
     A: StoreAdapter,
+    C: Capacity,
 {
     type Item = KeyReference;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.cursor >= BUCKETS {
+            if self.cursor >= self.store.buckets() {
                 return None;
             }
 
@@ -153,6 +439,7 @@ where
                 }
 
                 return Some(KeyReference {
+                    key_start: 0,
                     key_len,
                     val_len,
                     scratch,
@@ -163,6 +450,7 @@ where
 }
 
 pub struct KeyReference {
+    key_start: usize,
     key_len: usize,
     val_len: usize,
     scratch: [u8; MAX_KEY_LEN],
@@ -170,10 +458,16 @@ pub struct KeyReference {
 
 impl KeyReference {
     pub fn key(&self) -> &[u8] {
-        &self.scratch[..self.key_len]
+        &self.scratch[self.key_start..self.key_len]
     }
 
     pub fn val_len(&self) -> usize {
         self.val_len
     }
+
+    pub(crate) fn strip_prefix(self, prefix_len: usize) -> Self {
+        let mut res = self;
+        res.key_start = prefix_len;
+        res
+    }
 }
@@ -0,0 +1,328 @@
+use crate::adapters::*;
+use crate::*;
+use core::mem::size_of;
+
+const LEGACY_HEADER_SIZE: usize = 8;
+const LEGACY_BUCKET_SIZE: usize = 8;
+
+impl<E, A, const BUCKETS: usize, const SLOTS: usize> KVStore<A, BUCKETS, SLOTS>
+where
+    A: StoreAdapter<Error = E>,
+{
+    pub fn rehash(adapter: A, cfg: StoreConfig) -> Result<Self, Error<E>> {
+        Self::rehash_with(adapter, cfg, BUCKETS, [Slot::default(); SLOTS])
+    }
+
+    pub fn upgrade(adapter: A, cfg: StoreConfig) -> Result<Self, Error<E>> {
+        Self::upgrade_with(adapter, cfg, BUCKETS, [Slot::default(); SLOTS])
+    }
+}
+
+impl<'a, E, A> DynKVStore<'a, A>
+where
+    A: StoreAdapter<Error = E>,
+{
+    pub fn rehash(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        slots: &'a mut [Slot],
+    ) -> Result<Self, Error<E>> {
+        if buckets == 0 || buckets > u16::MAX as usize {
+            return Err(Error::InvalidCapacity);
+        }
+        Self::rehash_with(adapter, cfg, buckets, slots)
+    }
+
+    pub fn upgrade(adapter: A, cfg: StoreConfig, slots: &'a mut [Slot]) -> Result<Self, Error<E>> {
+        let mut adapter = adapter;
+        let buckets = Self::read_header(&mut adapter)?.buckets() as usize;
+        if buckets == 0 {
+            return Err(Error::InvalidCapacity);
+        }
+        Self::upgrade_with(adapter, cfg, buckets, slots)
+    }
+}
+
+impl<E, A, C> Store<A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    fn rehash_with(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        slots: C::Slots,
+    ) -> Result<Self, Error<E>> {
+        let mut adapter = adapter;
+        let header = Self::read_header(&mut adapter)?;
+        Self::check_format(&header, cfg.magic)?;
+        let layout = header.bucket_layout();
+        let requested = (cfg.hash_algorithm, cfg.probe_strategy);
+        let mut cfg = cfg.with_header(&header);
+        if let Some(hasher) = requested.0 {
+            cfg = cfg.hash_algorithm(hasher);
+        }
+        if let Some(strategy) = requested.1 {
+            cfg = cfg.probe_strategy(strategy);
+        }
+        let unchanged = header.buckets() as usize == buckets
+            && header.nonce() == cfg.nonce
+            && header.hash_algorithm() == cfg.hash_algorithm
+            && header.probe_strategy() == cfg.probe_strategy;
+
+        let data_end = data_start(layout, header.buckets() as usize) + header.data_size() as usize;
+        if data_end < data_start(layout, buckets) {
+            return Err(Error::InvalidCapacity);
+        }
+
+        let data_size = data_end - data_start(layout, buckets);
+        let mut store = Self::new(adapter, cfg, buckets, data_size, slots);
+        if unchanged && (header.record_kinds() || store.is_read_only()) {
+            return Ok(store);
+        }
+        if store.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+        store.rehash_table(header.buckets() as usize, header.record_kinds())?;
+        Ok(store)
+    }
+
+    fn upgrade_with(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        slots: C::Slots,
+    ) -> Result<Self, Error<E>> {
+        let mut adapter = adapter;
+        match Self::load_header(&mut adapter, cfg.magic, cfg.nonce) {
+            Ok(header) if header.buckets() as usize != buckets => {
+                return Err(Error::InvalidCapacity)
+            }
+            Ok(header) => {
+                let cfg = cfg.with_header(&header);
+                let data_size = header.data_size() as usize;
+                return Ok(Self::new(adapter, cfg, buckets, data_size, slots));
+            }
+            Err(Error::LegacyFormat) => {}
+            Err(err) => return Err(err),
+        }
+
+        let header = Self::read_header(&mut adapter)?;
+        Self::check_header(&header, cfg.magic, cfg.nonce)?;
+        if header.buckets() as usize != buckets {
+            return Err(Error::InvalidCapacity);
+        }
+
+        let cfg = cfg
+            .bucket_layout(BucketLayout::Compact)
+            .hash_algorithm(HashAlgorithm::Murmur3)
+            .probe_strategy(ProbeStrategy::Rehash);
+        let data_size = data_size(BucketLayout::Compact, buckets, adapter.max_address());
+        let mut store = Self::new(adapter, cfg, buckets, data_size, slots);
+        if store.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+        store.upgrade_legacy()?;
+        Ok(store)
+    }
+
+    fn upgrade_legacy(&mut self) -> Result<(), Error<E>> {
+        let legacy_start = LEGACY_HEADER_SIZE + LEGACY_BUCKET_SIZE * self.buckets;
+        self.make_room(
+            LEGACY_HEADER_SIZE,
+            self.buckets,
+            legacy_start,
+            self.data_start() - legacy_start,
+        )?;
+
+        for index in (0..self.buckets).rev() {
+            let raw = self.read_raw_bucket(LEGACY_HEADER_SIZE + LEGACY_BUCKET_SIZE * index)?;
+            self.write_bucket(index, raw)?;
+        }
+
+        self.alloc_loaded = false;
+        self.write_header()
+    }
+
+    fn rehash_table(&mut self, buckets: usize, record_kinds: bool) -> Result<(), Error<E>> {
+        let bucket_size = self.layout.bucket_size();
+
+        let table = size_of::<StoreHeader>();
+        let data_start = table + bucket_size * buckets;
+        let data_end = self.data_start() + self.data_size;
+
+        let mut used = 0;
+        for index in 0..buckets {
+            if self.read_raw_bucket(table + bucket_size * index)?.key_len() > 0 {
+                used += 1;
+            }
+        }
+        if used > self.buckets {
+            return Err(Error::IndexOverflow);
+        }
+
+        if self.data_start() > data_start {
+            self.make_room(table, buckets, data_start, self.data_start() - data_start)?;
+        }
+
+        let copy_len = bucket_size * buckets;
+        let region_start = usize::max(data_start, self.data_start());
+        let copy = self
+            .find_free_range(table, buckets, region_start, data_end, copy_len)?
+            .ok_or(Error::StoreOverflow)?;
+        self.copy_bytes(table, copy, copy_len)?;
+
+        let header = Self::read_header(&mut self.adapter)?;
+        let res = self
+            .reset()
+            .and_then(|_| self.reinsert_table(copy, buckets, record_kinds));
+        if res.is_err() {
+            self.copy_bytes(copy, table, copy_len)?;
+            self.adapter
+                .write(0, &header.into_bytes())
+                .map_err(Error::AdapterError)?;
+        }
+
+        if let Some(fill_with) = self.cfg.scrub {
+            self.fill_bytes(copy, copy_len, fill_with)?;
+        }
+        res
+    }
+
+    fn reinsert_table(
+        &mut self,
+        copy: Address,
+        buckets: usize,
+        record_kinds: bool,
+    ) -> Result<(), Error<E>> {
+        let bucket_size = self.layout.bucket_size();
+        for index in 0..buckets {
+            let mut raw = self.read_raw_bucket(copy + bucket_size * index)?;
+            if !record_kinds {
+                raw.set_compressed(false);
+            }
+            let key_len = raw.key_len() as usize;
+            if key_len == 0 {
+                continue;
+            }
+
+            self.adapter
+                .read(raw.address() as Address, &mut self.scratch[..key_len])
+                .map_err(Error::AdapterError)?;
+            let probe = self.probe(self.cfg.max_hops, &self.scratch[..key_len]);
+            let hash = probe.tag();
+            let placement = self.plan_placement(probe)?;
+            self.make_place(&placement)?;
+
+            raw.set_hash(hash);
+            self.write_bucket(placement.index(), raw)?;
+        }
+        Ok(())
+    }
+
+    fn find_free_range(
+        &mut self,
+        table: Address,
+        buckets: usize,
+        start: Address,
+        end: Address,
+        len: usize,
+    ) -> Result<Option<Address>, Error<E>> {
+        for index in 0..=buckets {
+            let candidate = if index == buckets {
+                start
+            } else {
+                let raw = self.read_raw_bucket(table + self.layout.bucket_size() * index)?;
+                if raw.key_len() == 0 {
+                    continue;
+                }
+                raw.address() as Address + raw.key_len() as usize + raw.val_len() as usize
+            };
+
+            if candidate < start || candidate + len > end {
+                continue;
+            }
+
+            let mut in_use = false;
+            for index in 0..buckets {
+                let raw = self.read_raw_bucket(table + self.layout.bucket_size() * index)?;
+                let addr = raw.address() as Address;
+                let record_len = raw.key_len() as usize + raw.val_len() as usize;
+                if raw.key_len() > 0 && addr < candidate + len && candidate < addr + record_len {
+                    in_use = true;
+                    break;
+                }
+            }
+
+            if !in_use {
+                return Ok(Some(candidate));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn make_room(
+        &mut self,
+        table: Address,
+        buckets: usize,
+        start: Address,
+        len: usize,
+    ) -> Result<(), Error<E>> {
+        let mut run_end = start;
+        loop {
+            let mut extended = false;
+            for index in 0..buckets {
+                let raw = self.read_raw_bucket(table + self.layout.bucket_size() * index)?;
+                let addr = raw.address() as Address;
+                let end = addr + raw.key_len() as usize + raw.val_len() as usize;
+                if raw.key_len() > 0 && addr < run_end + len && end > run_end {
+                    run_end = end;
+                    extended = true;
+                }
+            }
+
+            if !extended {
+                break;
+            }
+        }
+
+        if run_end + len > self.data_start() + self.data_size {
+            return Err(Error::StoreOverflow);
+        }
+
+        let mut limit = run_end;
+        loop {
+            let mut next: Option<(Address, RawBucket)> = None;
+            for index in 0..buckets {
+                let offset = table + self.layout.bucket_size() * index;
+                let raw = self.read_raw_bucket(offset)?;
+                let addr = raw.address() as Address;
+                if raw.key_len() == 0 || addr < start || addr >= limit {
+                    continue;
+                }
+                if matches!(next, Some((_, ref top)) if top.address() as Address > addr) {
+                    continue;
+                }
+                next = Some((offset, raw));
+            }
+
+            let (offset, mut raw) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            let addr = raw.address() as Address;
+            let record_len = raw.key_len() as usize + raw.val_len() as usize;
+            self.copy_bytes(addr, addr + len, record_len)?;
+
+            raw.set_address((addr + len) as u32);
+            self.write_raw_bucket(offset, raw)?;
+            limit = addr;
+        }
+
+        Ok(())
+    }
+}
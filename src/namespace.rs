@@ -0,0 +1,319 @@
+use crate::adapters::*;
+use crate::*;
+
+pub struct Namespace<'a, A, C>
+where
+    A: StoreAdapter,
+    C: Capacity,
+{
+    store: &'a mut Store<A, C>,
+    prefix: &'a [u8],
+    quota: Option<usize>,
+    usage: Option<usize>,
+}
+
+impl<'a, E, A, C> Namespace<'a, A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub fn new(store: &'a mut Store<A, C>, prefix: &'a [u8]) -> Self {
+        Self {
+            store,
+            prefix,
+            quota: None,
+            usage: None,
+        }
+    }
+
+    pub fn quota(self, quota: usize) -> Self {
+        let mut res = self;
+        res.quota = Some(quota);
+        res
+    }
+
+    pub fn prefix(&self) -> &[u8] {
+        self.prefix
+    }
+
+    pub fn usage(&mut self) -> Result<usize, Error<E>> {
+        if let Some(usage) = self.usage {
+            return Ok(usage);
+        }
+
+        let usage = self
+            .store
+            .keys_with_prefix(self.prefix)
+            .map(|key_ref| key_ref.key().len() + key_ref.val_len())
+            .sum();
+        self.usage = Some(usage);
+        Ok(usage)
+    }
+
+    pub fn alloc(
+        &mut self,
+        key: &[u8],
+        val_len: usize,
+        fill_with: Option<u8>,
+    ) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + val_len))?;
+        let bucket = self.store.alloc(key, val_len, fill_with)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + val.len()))?;
+        let bucket = self.store.insert(key, val)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn insert_if_absent(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + val.len()))?;
+        let bucket = self.store.insert_if_absent(key, val)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn replace_if_exists(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + val.len()))?;
+        let bucket = self.store.replace_if_exists(key, val)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: &[u8],
+        val: &[u8],
+    ) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + val.len()))?;
+        let bucket = self.store.compare_and_swap(key, expected, val)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn append(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = match self.quota {
+            Some(_) => {
+                let bucket = self.store.lookup(key)?;
+                self.reserve(key, Some(bucket.record_len() + val.len()))?
+            }
+            None => self.reserve(key, None)?,
+        };
+        let bucket = self.store.append(key, val)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn patch(&mut self, key: &[u8], offset: usize, patch: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = match self.quota {
+            Some(_) => {
+                let bucket = self.store.lookup(key)?;
+                let val_len = usize::max(offset + patch.len(), bucket.val_len());
+                self.reserve(key, Some(bucket.key_len() + val_len))?
+            }
+            None => self.reserve(key, None)?,
+        };
+        let bucket = self.store.patch(key, offset, patch)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn truncate(&mut self, key: &[u8], new_len: usize) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, None)?;
+        let bucket = self.store.truncate(key, new_len)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn resize(
+        &mut self,
+        key: &[u8],
+        new_len: usize,
+        fill_with: u8,
+    ) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, Some(key.len() + new_len))?;
+        let bucket = self.store.resize(key, new_len, fill_with)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn rename(&mut self, old_key: &[u8], new_key: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut old_scratch = [0; MAX_KEY_LEN];
+        let mut new_scratch = [0; MAX_KEY_LEN];
+        let old_key = prefixed(&mut old_scratch, self.prefix, old_key)?;
+        let new_key = prefixed(&mut new_scratch, self.prefix, new_key)?;
+        let released = match self.quota {
+            Some(_) => {
+                let bucket = self.store.lookup(old_key)?;
+                self.reserve(old_key, Some(new_key.len() + bucket.val_len()))?
+            }
+            None => self.reserve(old_key, None)?,
+        };
+        let bucket = self.store.rename(old_key, new_key)?;
+        self.track_usage(released, bucket.record_len());
+        Ok(bucket)
+    }
+
+    pub fn load(&mut self, key: &[u8], buf: &mut [u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.load(key, buf)
+    }
+
+    pub fn load_slice<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b [u8], Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.load_slice(key, buf)
+    }
+
+    pub fn load_str<'b>(&mut self, key: &[u8], buf: &'b mut [u8]) -> Result<&'b str, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.load_str(key, buf)
+    }
+
+    pub fn load_at(
+        &mut self,
+        key: &[u8],
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.load_at(key, buf, offset)
+    }
+
+    pub fn exists(&mut self, key: &[u8]) -> Result<bool, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.exists(key)
+    }
+
+    pub fn lookup(&mut self, key: &[u8]) -> Result<Bucket, Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        self.store.lookup(key)
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Result<(), Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, None)?;
+        self.store.remove(key)?;
+        self.track_usage(released, 0);
+        Ok(())
+    }
+
+    pub fn erase(&mut self, key: &[u8], fill_with: u8) -> Result<(), Error<E>> {
+        let mut scratch = [0; MAX_KEY_LEN];
+        let key = prefixed(&mut scratch, self.prefix, key)?;
+        let released = self.reserve(key, None)?;
+        self.store.erase(key, fill_with)?;
+        self.track_usage(released, 0);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<usize, Error<E>> {
+        let removed = self.store.remove_prefix(self.prefix)?;
+        if self.usage.is_some() {
+            self.usage = Some(0);
+        }
+        Ok(removed)
+    }
+
+    pub fn keys(&mut self) -> NamespaceKeysIterator<'_, 'a, A, C> {
+        NamespaceKeysIterator {
+            inner: self.store.keys_with_prefix(self.prefix),
+            prefix_len: self.prefix.len(),
+        }
+    }
+
+    fn reserve(&mut self, key: &[u8], record_len: Option<usize>) -> Result<usize, Error<E>> {
+        if self.quota.is_none() && self.usage.is_none() {
+            return Ok(0);
+        }
+
+        let usage = self.usage()?;
+        let released = match self.store.lookup(key) {
+            Ok(bucket) => bucket.record_len(),
+            Err(Error::KeyNotFound) => 0,
+            Err(err) => return Err(err),
+        };
+
+        if let (Some(quota), Some(record_len)) = (self.quota, record_len) {
+            if usage - released + record_len > quota {
+                return Err(Error::QuotaExceeded);
+            }
+        }
+
+        Ok(released)
+    }
+
+    fn track_usage(&mut self, released: usize, record_len: usize) {
+        if let Some(usage) = self.usage.as_mut() {
+            *usage = *usage - released + record_len;
+        }
+    }
+}
+
+pub struct NamespaceKeysIterator<'a, 'b, A, C>
+where
+    A: StoreAdapter,
+    C: Capacity,
+{
+    inner: KeysIterator<'a, 'b, A, C>,
+    prefix_len: usize,
+}
+
+impl<'a, 'b, A, C> Iterator for NamespaceKeysIterator<'a, 'b, A, C>
+where
+    A: StoreAdapter,
+    C: Capacity,
+{
+    type Item = KeyReference;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|key_ref| key_ref.strip_prefix(self.prefix_len))
+    }
+}
+
+fn prefixed<'a, E>(
+    scratch: &'a mut [u8; MAX_KEY_LEN],
+    prefix: &[u8],
+    key: &[u8],
+) -> Result<&'a [u8], Error<E>> {
+    if key.is_empty() {
+        return Err(Error::InvalidKey);
+    }
+    let key_len = prefix.len() + key.len();
+    if key_len > MAX_KEY_LEN {
+        return Err(Error::KeyOverflow);
+    }
+    scratch[..prefix.len()].copy_from_slice(prefix);
+    scratch[prefix.len()..key_len].copy_from_slice(key);
+    Ok(&scratch[..key_len])
+}
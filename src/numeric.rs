@@ -0,0 +1,141 @@
+use crate::adapters::*;
+use crate::*;
+use byteorder::ByteOrder;
+
+impl<E, A, C> Store<A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub fn get_u8(&mut self, key: &[u8]) -> Result<u8, Error<E>> {
+        let mut buf = [0; 1];
+        self.load_fixed(key, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    pub fn set_u8(&mut self, key: &[u8], val: u8) -> Result<Bucket, Error<E>> {
+        self.store_fixed(key, &[val])
+    }
+
+    pub fn get_u16<B: ByteOrder>(&mut self, key: &[u8]) -> Result<u16, Error<E>> {
+        let mut buf = [0; 2];
+        self.load_fixed(key, &mut buf)?;
+        Ok(B::read_u16(&buf))
+    }
+
+    pub fn set_u16<B: ByteOrder>(&mut self, key: &[u8], val: u16) -> Result<Bucket, Error<E>> {
+        let mut buf = [0; 2];
+        B::write_u16(&mut buf, val);
+        self.store_fixed(key, &buf)
+    }
+
+    pub fn get_u32<B: ByteOrder>(&mut self, key: &[u8]) -> Result<u32, Error<E>> {
+        let mut buf = [0; 4];
+        self.load_fixed(key, &mut buf)?;
+        Ok(B::read_u32(&buf))
+    }
+
+    pub fn set_u32<B: ByteOrder>(&mut self, key: &[u8], val: u32) -> Result<Bucket, Error<E>> {
+        let mut buf = [0; 4];
+        B::write_u32(&mut buf, val);
+        self.store_fixed(key, &buf)
+    }
+
+    pub fn get_u64<B: ByteOrder>(&mut self, key: &[u8]) -> Result<u64, Error<E>> {
+        let mut buf = [0; 8];
+        self.load_fixed(key, &mut buf)?;
+        Ok(B::read_u64(&buf))
+    }
+
+    pub fn set_u64<B: ByteOrder>(&mut self, key: &[u8], val: u64) -> Result<Bucket, Error<E>> {
+        let mut buf = [0; 8];
+        B::write_u64(&mut buf, val);
+        self.store_fixed(key, &buf)
+    }
+
+    pub fn get_i32<B: ByteOrder>(&mut self, key: &[u8]) -> Result<i32, Error<E>> {
+        let mut buf = [0; 4];
+        self.load_fixed(key, &mut buf)?;
+        Ok(B::read_i32(&buf))
+    }
+
+    pub fn set_i32<B: ByteOrder>(&mut self, key: &[u8], val: i32) -> Result<Bucket, Error<E>> {
+        let mut buf = [0; 4];
+        B::write_i32(&mut buf, val);
+        self.store_fixed(key, &buf)
+    }
+
+    pub fn get_i64<B: ByteOrder>(&mut self, key: &[u8]) -> Result<i64, Error<E>> {
+        let mut buf = [0; 8];
+        self.load_fixed(key, &mut buf)?;
+        Ok(B::read_i64(&buf))
+    }
+
+    pub fn set_i64<B: ByteOrder>(&mut self, key: &[u8], val: i64) -> Result<Bucket, Error<E>> {
+        let mut buf = [0; 8];
+        B::write_i64(&mut buf, val);
+        self.store_fixed(key, &buf)
+    }
+
+    pub fn increment<B: ByteOrder>(&mut self, key: &[u8], delta: i64) -> Result<i64, Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+
+        let mut buf = [0; 8];
+        match self.lookup(key) {
+            Ok(bucket) => {
+                if bucket.is_compressed() {
+                    return Err(Error::CompressedValue);
+                }
+                let len = bucket.val_len();
+                if !matches!(len, 1 | 2 | 4 | 8) {
+                    return Err(Error::InvalidValueLength);
+                }
+
+                self.adapter()
+                    .read(bucket.val_address(), &mut buf[..len])
+                    .map_err(Error::AdapterError)?;
+                let mask = u64::MAX >> (64 - 8 * len);
+                let val = B::read_uint(&buf[..len], len).wrapping_add(delta as u64) & mask;
+                B::write_uint(&mut buf[..len], val, len);
+                self.patch_value(bucket, 0, &buf[..len])?;
+
+                Ok(val as i64)
+            }
+            Err(Error::KeyNotFound) => {
+                B::write_i64(&mut buf, delta);
+                self.insert(key, &buf)?;
+                Ok(delta)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn load_fixed(&mut self, key: &[u8], buf: &mut [u8]) -> Result<(), Error<E>> {
+        let bucket = self.lookup(key)?;
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
+        }
+        if bucket.val_len() != buf.len() {
+            return Err(Error::InvalidValueLength);
+        }
+        self.adapter()
+            .read(bucket.val_address(), buf)
+            .map_err(Error::AdapterError)
+    }
+
+    fn store_fixed(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+
+        match self.lookup(key) {
+            Ok(bucket) if bucket.val_len() == val.len() && !bucket.is_compressed() => {
+                self.patch_value(bucket, 0, val)
+            }
+            Ok(_) | Err(Error::KeyNotFound) => self.insert(key, val),
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::adapters::*;
+use crate::*;
+
+const MAX_KICKS: usize = 16;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum ProbeStrategy {
+    #[default]
+    Rehash,
+    Linear,
+    RobinHood,
+    Cuckoo,
+}
+
+impl ProbeStrategy {
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(ProbeStrategy::Rehash),
+            1 => Some(ProbeStrategy::Linear),
+            2 => Some(ProbeStrategy::RobinHood),
+            3 => Some(ProbeStrategy::Cuckoo),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            ProbeStrategy::Rehash => 0,
+            ProbeStrategy::Linear => 1,
+            ProbeStrategy::RobinHood => 2,
+            ProbeStrategy::Cuckoo => 3,
+        }
+    }
+}
+
+pub struct Probe {
+    strategy: ProbeStrategy,
+    hopper: Grasshopper<HashAlgorithm>,
+    tag: u64,
+    buckets: usize,
+    hops: usize,
+    step: usize,
+    home: usize,
+    alt: usize,
+}
+
+impl Probe {
+    pub fn new(
+        strategy: ProbeStrategy,
+        hasher: HashAlgorithm,
+        buckets: usize,
+        hops: usize,
+        nonce: u16,
+        key: &[u8],
+        tag_mask: u64,
+    ) -> Self {
+        let hopper = Grasshopper::with_hasher(hasher, buckets, hops, nonce, key);
+        let tag = hopper.tag() & tag_mask;
+        let home = home_index(tag, buckets);
+
+        Self {
+            strategy,
+            hopper,
+            tag,
+            buckets,
+            hops: usize::min(hops, buckets),
+            step: 0,
+            home,
+            alt: cuckoo_pair(hasher, tag, home, buckets),
+        }
+    }
+
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+}
+
+impl Iterator for Probe {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.strategy {
+            ProbeStrategy::Rehash => return self.hopper.next(),
+            ProbeStrategy::Linear | ProbeStrategy::RobinHood if self.step < self.hops => {}
+            ProbeStrategy::Cuckoo
+                if self.step == 0 || (self.step == 1 && self.alt != self.home) => {}
+            _ => return None,
+        }
+
+        let index = match self.strategy {
+            ProbeStrategy::Cuckoo if self.step == 1 => self.alt,
+            _ => (self.home + self.step) % self.buckets,
+        };
+        self.step += 1;
+        Some(index)
+    }
+}
+
+pub(crate) fn home_index(tag: u64, buckets: usize) -> usize {
+    (tag % buckets as u64) as usize
+}
+
+pub(crate) fn cuckoo_pair(hasher: HashAlgorithm, tag: u64, index: usize, buckets: usize) -> usize {
+    let mix = hasher.hash(0, &[&tag.to_be_bytes()]) % buckets as u64;
+    (mix as usize + buckets - index) % buckets
+}
+
+pub(crate) enum Placement {
+    Empty(usize),
+    Shift {
+        start: usize,
+        end: usize,
+    },
+    Kick {
+        path: [usize; MAX_KICKS + 1],
+        len: usize,
+    },
+}
+
+impl Placement {
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Placement::Empty(index) => *index,
+            Placement::Shift { start, .. } => *start,
+            Placement::Kick { path, .. } => path[0],
+        }
+    }
+}
+
+impl<E, A, C> Store<A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub fn probe_strategy(&self) -> ProbeStrategy {
+        self.strategy
+    }
+
+    pub(crate) fn probe(&self, hops: usize, key: &[u8]) -> Probe {
+        Probe::new(
+            self.strategy,
+            self.hasher,
+            self.buckets,
+            hops,
+            self.cfg.nonce,
+            key,
+            self.tag_mask(),
+        )
+    }
+
+    fn tag_mask(&self) -> u64 {
+        if self.record_kinds {
+            self.layout.tag_mask() & !self.layout.kind_bit()
+        } else {
+            self.layout.tag_mask()
+        }
+    }
+
+    pub(crate) fn probe_distance(&self, raw: &RawBucket, index: usize) -> usize {
+        (index + self.buckets - home_index(raw.hash(), self.buckets)) % self.buckets
+    }
+
+    pub(crate) fn chain_ends(&self, raw: &RawBucket, index: usize, distance: usize) -> bool {
+        match self.strategy {
+            ProbeStrategy::Rehash | ProbeStrategy::Linear => self.tombstones && raw.is_empty(),
+            ProbeStrategy::RobinHood => {
+                raw.key_len() == 0 || self.probe_distance(raw, index) < distance
+            }
+            ProbeStrategy::Cuckoo => false,
+        }
+    }
+
+    pub(crate) fn plan_placement(&mut self, probe: Probe) -> Result<Placement, Error<E>> {
+        match self.strategy {
+            ProbeStrategy::Rehash | ProbeStrategy::Linear => {
+                for index in probe {
+                    if self.load_bucket(index)?.key_len() == 0 {
+                        return Ok(Placement::Empty(index));
+                    }
+                }
+                Err(Error::IndexOverflow)
+            }
+            ProbeStrategy::RobinHood => self.plan_robin_hood(probe),
+            ProbeStrategy::Cuckoo => self.plan_cuckoo(probe),
+        }
+    }
+
+    pub(crate) fn make_place(&mut self, placement: &Placement) -> Result<(), Error<E>> {
+        match placement {
+            Placement::Empty(_) => {}
+            Placement::Shift { start, end } => {
+                let mut index = *end;
+                while index != *start {
+                    let prev = (index + self.buckets - 1) % self.buckets;
+                    let raw = self.load_bucket(prev)?;
+                    self.write_bucket(index, raw)?;
+                    index = prev;
+                }
+            }
+            Placement::Kick { path, len } => {
+                for step in (1..*len).rev() {
+                    let raw = self.load_bucket(path[step - 1])?;
+                    self.write_bucket(path[step], raw)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear_bucket(&mut self, index: usize) -> Result<(), Error<E>> {
+        if matches!(self.strategy, ProbeStrategy::Rehash | ProbeStrategy::Linear) {
+            return self.write_bucket(index, RawBucket::tombstone());
+        }
+
+        let mut index = index;
+        if self.strategy == ProbeStrategy::RobinHood {
+            loop {
+                let next = (index + 1) % self.buckets;
+                let raw = self.load_bucket(next)?;
+                if raw.key_len() == 0 || self.probe_distance(&raw, next) == 0 {
+                    break;
+                }
+                self.write_bucket(index, raw)?;
+                index = next;
+            }
+        }
+        self.write_bucket(index, RawBucket::new())
+    }
+
+    fn plan_robin_hood(&mut self, probe: Probe) -> Result<Placement, Error<E>> {
+        let max_hops = usize::min(self.cfg.max_hops, self.buckets);
+        for (distance, index) in probe.enumerate() {
+            if distance >= max_hops {
+                break;
+            }
+
+            let raw = self.load_bucket(index)?;
+            if raw.key_len() == 0 {
+                return Ok(Placement::Empty(index));
+            }
+            if self.probe_distance(&raw, index) >= distance {
+                continue;
+            }
+
+            let mut end = index;
+            loop {
+                let raw = self.load_bucket(end)?;
+                if raw.key_len() == 0 {
+                    return Ok(Placement::Shift { start: index, end });
+                }
+                if self.probe_distance(&raw, end) + 1 >= max_hops {
+                    return Err(Error::IndexOverflow);
+                }
+                end = (end + 1) % self.buckets;
+                if end == index {
+                    return Err(Error::IndexOverflow);
+                }
+            }
+        }
+
+        Err(Error::IndexOverflow)
+    }
+
+    fn plan_cuckoo(&mut self, probe: Probe) -> Result<Placement, Error<E>> {
+        let mut starts = [0; 2];
+        let mut count = 0;
+        for index in probe {
+            if self.load_bucket(index)?.key_len() == 0 {
+                return Ok(Placement::Empty(index));
+            }
+            starts[count] = index;
+            count += 1;
+        }
+
+        for start in &starts[..count] {
+            let mut path = [0; MAX_KICKS + 1];
+            path[0] = *start;
+            for len in 1..=MAX_KICKS {
+                let raw = self.load_bucket(path[len - 1])?;
+                let next = cuckoo_pair(self.hasher, raw.hash(), path[len - 1], self.buckets);
+                if path[..len].contains(&next) {
+                    break;
+                }
+
+                path[len] = next;
+                if self.load_bucket(next)?.key_len() == 0 {
+                    return Ok(Placement::Kick { path, len: len + 1 });
+                }
+            }
+        }
+
+        Err(Error::IndexOverflow)
+    }
+}
@@ -1,19 +1,31 @@
 use crate::adapters::*;
 use crate::*;
+use core::marker::PhantomData;
 use core::mem::size_of;
 
 #[cfg(feature = "serde")]
-use heapless::Vec;
-#[cfg(feature = "serde")]
-use postcard::{from_bytes, to_vec};
-#[cfg(feature = "serde")]
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Clone, Copy)]
+enum WriteCondition<'a> {
+    Always,
+    Absent,
+    Present,
+    Matches(&'a [u8]),
+}
 
 pub struct StoreConfig {
-    magic: u32,
-    nonce: u16,
-    max_hops: usize,
-    alloc_strategy: AllocStrategy,
+    pub(crate) magic: u32,
+    pub(crate) nonce: u16,
+    pub(crate) max_hops: usize,
+    pub(crate) alloc_strategy: AllocStrategy,
+    pub(crate) bucket_layout: Option<BucketLayout>,
+    pub(crate) hash_algorithm: Option<HashAlgorithm>,
+    pub(crate) probe_strategy: Option<ProbeStrategy>,
+    pub(crate) tombstones: bool,
+    pub(crate) nonce_slot: bool,
+    pub(crate) record_kinds: bool,
+    pub(crate) scrub: Option<u8>,
 }
 
 impl StoreConfig {
@@ -23,6 +35,13 @@ impl StoreConfig {
             max_hops,
             nonce: 0,
             alloc_strategy: AllocStrategy::default(),
+            bucket_layout: None,
+            hash_algorithm: None,
+            probe_strategy: None,
+            tombstones: false,
+            nonce_slot: false,
+            record_kinds: false,
+            scrub: None,
         }
     }
 
@@ -37,51 +56,336 @@ impl StoreConfig {
         res.alloc_strategy = alloc_strategy;
         res
     }
+
+    pub fn bucket_layout(self, bucket_layout: BucketLayout) -> Self {
+        let mut res = self;
+        res.bucket_layout = Some(bucket_layout);
+        res
+    }
+
+    pub fn hash_algorithm(self, hash_algorithm: HashAlgorithm) -> Self {
+        let mut res = self;
+        res.hash_algorithm = Some(hash_algorithm);
+        res
+    }
+
+    pub fn probe_strategy(self, probe_strategy: ProbeStrategy) -> Self {
+        let mut res = self;
+        res.probe_strategy = Some(probe_strategy);
+        res
+    }
+
+    pub fn secure_erase(self, fill_with: u8) -> Self {
+        let mut res = self;
+        res.scrub = Some(fill_with);
+        res
+    }
+
+    pub(crate) fn with_header(self, header: &StoreHeader) -> Self {
+        let mut res = self;
+        res.bucket_layout = Some(header.bucket_layout());
+        res.hash_algorithm = header.hash_algorithm();
+        res.probe_strategy = header.probe_strategy();
+        res.tombstones = header.tombstones();
+        res.nonce_slot = header.nonce_slot();
+        res.record_kinds = header.record_kinds();
+        res
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StoreStats {
+    pub buckets: usize,
+    pub used_buckets: usize,
+    pub max_probe_distance: usize,
+    pub data_size: usize,
+    pub bytes_used: usize,
+    pub bytes_free: usize,
+    pub largest_free: usize,
+    pub slots: usize,
+    pub used_slots: usize,
+}
+
+impl StoreStats {
+    pub fn load_factor(&self) -> f32 {
+        if self.buckets == 0 {
+            return 0.0;
+        }
+        self.used_buckets as f32 / self.buckets as f32
+    }
+
+    pub fn fragmentation(&self) -> f32 {
+        if self.bytes_free == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free as f32 / self.bytes_free as f32
+    }
 }
 
-pub struct KVStore<A, const BUCKETS: usize, const SLOTS: usize>
+pub trait Capacity {
+    type Slots;
+    type Alloc: Allocator;
+
+    fn allocator(
+        slots: Self::Slots,
+        alloc_strategy: AllocStrategy,
+        start: Address,
+        space: usize,
+    ) -> Self::Alloc;
+}
+
+pub struct FixedCapacity<const BUCKETS: usize, const SLOTS: usize>;
+
+impl<const BUCKETS: usize, const SLOTS: usize> Capacity for FixedCapacity<BUCKETS, SLOTS> {
+    type Slots = [Slot; SLOTS];
+    type Alloc = Alloc<[Slot; SLOTS]>;
+
+    fn allocator(
+        slots: Self::Slots,
+        alloc_strategy: AllocStrategy,
+        start: Address,
+        space: usize,
+    ) -> Self::Alloc {
+        Alloc::new(slots, alloc_strategy, start, space)
+    }
+}
+
+pub struct DynamicCapacity<'a>(PhantomData<&'a mut [Slot]>);
+
+impl<'a> Capacity for DynamicCapacity<'a> {
+    type Slots = &'a mut [Slot];
+    type Alloc = Alloc<&'a mut [Slot]>;
+
+    fn allocator(
+        slots: Self::Slots,
+        alloc_strategy: AllocStrategy,
+        start: Address,
+        space: usize,
+    ) -> Self::Alloc {
+        Alloc::new(slots, alloc_strategy, start, space)
+    }
+}
+
+pub struct BlockCapacity<const BUCKETS: usize, const BLOCK_SIZE: usize, const WORDS: usize>;
+
+impl<const BUCKETS: usize, const BLOCK_SIZE: usize, const WORDS: usize> Capacity
+    for BlockCapacity<BUCKETS, BLOCK_SIZE, WORDS>
+{
+    type Slots = [u32; WORDS];
+    type Alloc = BlockAlloc<[u32; WORDS]>;
+
+    fn allocator(
+        slots: Self::Slots,
+        alloc_strategy: AllocStrategy,
+        start: Address,
+        space: usize,
+    ) -> Self::Alloc {
+        BlockAlloc::new(slots, BLOCK_SIZE, alloc_strategy, start, space)
+    }
+}
+
+pub struct Store<A, C>
 where
     A: StoreAdapter,
+    C: Capacity,
 {
-    adapter: A,
-    cfg: StoreConfig,
-    alloc: Option<Alloc<SLOTS>>,
-    scratch: [u8; MAX_KEY_LEN],
+    pub(crate) adapter: A,
+    pub(crate) cfg: StoreConfig,
+    pub(crate) alloc: C::Alloc,
+    pub(crate) alloc_loaded: bool,
+    pub(crate) scratch: [u8; MAX_KEY_LEN],
+    pub(crate) layout: BucketLayout,
+    pub(crate) hasher: HashAlgorithm,
+    pub(crate) strategy: ProbeStrategy,
+    pub(crate) tombstones: bool,
+    pub(crate) nonce_slot: bool,
+    pub(crate) record_kinds: bool,
+    pub(crate) buckets: usize,
+    pub(crate) data_size: usize,
 }
 
+pub type KVStore<A, const BUCKETS: usize, const SLOTS: usize> =
+    Store<A, FixedCapacity<BUCKETS, SLOTS>>;
+
 pub type ReadOnlyKVStore<A, const BUCKETS: usize> = KVStore<A, BUCKETS, 0>;
 
+pub type DynKVStore<'a, A> = Store<A, DynamicCapacity<'a>>;
+
+pub type BlockKVStore<A, const BUCKETS: usize, const BLOCK_SIZE: usize, const WORDS: usize> =
+    Store<A, BlockCapacity<BUCKETS, BLOCK_SIZE, WORDS>>;
+
+pub(crate) fn data_start(layout: BucketLayout, buckets: usize) -> Address {
+    size_of::<StoreHeader>() + layout.bucket_size() * buckets
+}
+
+pub(crate) fn data_size(layout: BucketLayout, buckets: usize, max_address: Address) -> usize {
+    usize::min(max_address, layout.max_store_size()).saturating_sub(data_start(layout, buckets))
+}
+
 impl<E, A, const BUCKETS: usize, const SLOTS: usize> KVStore<A, BUCKETS, SLOTS>
 where
     A: StoreAdapter<Error = E>,
 {
-    const DATA_START: Address = size_of::<StoreHeader>() + size_of::<RawBucket>() * BUCKETS;
+    pub fn open(adapter: A, cfg: StoreConfig, create_new: bool) -> Result<Self, Error<E>> {
+        let mut adapter = adapter;
+        match Self::load_header(&mut adapter, cfg.magic, cfg.nonce) {
+            Ok(header) if header.buckets() as usize != BUCKETS => Err(Error::InvalidCapacity),
+            Ok(header) => Ok(Self::new(
+                adapter,
+                cfg.with_header(&header),
+                BUCKETS,
+                header.data_size() as usize,
+                [Slot::default(); SLOTS],
+            )),
+            Err(Error::StoreNotFound) if create_new => Self::create(adapter, cfg),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn create(adapter: A, cfg: StoreConfig) -> Result<Self, Error<E>> {
+        Self::create_with(adapter, cfg, BUCKETS, [Slot::default(); SLOTS])
+    }
+}
 
+impl<E, A, const BUCKETS: usize, const BLOCK_SIZE: usize, const WORDS: usize>
+    BlockKVStore<A, BUCKETS, BLOCK_SIZE, WORDS>
+where
+    A: StoreAdapter<Error = E>,
+{
     pub fn open(adapter: A, cfg: StoreConfig, create_new: bool) -> Result<Self, Error<E>> {
         let mut adapter = adapter;
         match Self::load_header(&mut adapter, cfg.magic, cfg.nonce) {
-            Ok(_) => Ok(Self {
-                alloc: None,
-                scratch: [0; MAX_KEY_LEN],
+            Ok(header) if header.buckets() as usize != BUCKETS => Err(Error::InvalidCapacity),
+            Ok(header) => Ok(Self::new(
                 adapter,
-                cfg,
-            }),
+                cfg.with_header(&header),
+                BUCKETS,
+                header.data_size() as usize,
+                [0; WORDS],
+            )),
             Err(Error::StoreNotFound) if create_new => Self::create(adapter, cfg),
             Err(err) => Err(err),
         }
     }
 
     pub fn create(adapter: A, cfg: StoreConfig) -> Result<Self, Error<E>> {
-        let mut res = Self {
-            alloc: None,
-            scratch: [0; MAX_KEY_LEN],
+        Self::create_with(adapter, cfg, BUCKETS, [0; WORDS])
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.alloc.block_size()
+    }
+}
+
+impl<'a, E, A> DynKVStore<'a, A>
+where
+    A: StoreAdapter<Error = E>,
+{
+    pub fn open(adapter: A, cfg: StoreConfig, slots: &'a mut [Slot]) -> Result<Self, Error<E>> {
+        let mut adapter = adapter;
+        let header = Self::load_header(&mut adapter, cfg.magic, cfg.nonce)?;
+        if header.buckets() == 0 {
+            return Err(Error::InvalidCapacity);
+        }
+
+        Ok(Self::new(
             adapter,
-            cfg,
-        };
+            cfg.with_header(&header),
+            header.buckets() as usize,
+            header.data_size() as usize,
+            slots,
+        ))
+    }
+
+    pub fn create(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        slots: &'a mut [Slot],
+    ) -> Result<Self, Error<E>> {
+        Self::create_with(adapter, cfg, buckets, slots)
+    }
+}
+
+impl<E, A, C> Store<A, C>
+where
+    A: StoreAdapter<Error = E>,
+    C: Capacity,
+{
+    pub(crate) fn create_with(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        slots: C::Slots,
+    ) -> Result<Self, Error<E>> {
+        if buckets == 0 || buckets > u16::MAX as usize {
+            return Err(Error::InvalidCapacity);
+        }
+
+        let layout = cfg
+            .bucket_layout
+            .unwrap_or_else(|| BucketLayout::for_store_size(adapter.max_address()));
+        let cfg = cfg.bucket_layout(layout);
+        let data_size = data_size(layout, buckets, adapter.max_address());
+        let mut res = Self::new(adapter, cfg, buckets, data_size, slots);
         res.reset()?;
         Ok(res)
     }
 
+    pub(crate) fn new(
+        adapter: A,
+        cfg: StoreConfig,
+        buckets: usize,
+        data_size: usize,
+        slots: C::Slots,
+    ) -> Self {
+        let layout = cfg.bucket_layout.unwrap_or_default();
+        Self {
+            alloc: C::allocator(
+                slots,
+                cfg.alloc_strategy,
+                data_start(layout, buckets),
+                data_size,
+            ),
+            alloc_loaded: false,
+            scratch: [0; MAX_KEY_LEN],
+            layout,
+            hasher: cfg.hash_algorithm.unwrap_or_default(),
+            strategy: cfg.probe_strategy.unwrap_or_default(),
+            tombstones: cfg.tombstones,
+            nonce_slot: cfg.nonce_slot,
+            record_kinds: cfg.record_kinds,
+            adapter,
+            cfg,
+            buckets,
+            data_size,
+        }
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.buckets
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.alloc.capacity() == 0
+    }
+
+    pub fn bucket_layout(&self) -> BucketLayout {
+        self.layout
+    }
+
+    pub(crate) fn data_start(&self) -> Address {
+        data_start(self.layout, self.buckets)
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hasher
+    }
+
+    pub(crate) fn bucket_offset(&self, index: usize) -> Address {
+        size_of::<StoreHeader>() + self.layout.bucket_size() * index
+    }
+
     pub fn adapter(&mut self) -> &mut A {
         &mut self.adapter
     }
@@ -93,30 +397,24 @@ where
     pub fn reset(&mut self) -> Result<(), Error<E>> {
         const ERASE_BATCH_SIZE: usize = 32;
 
-        let zeroes = [0; size_of::<RawBucket>() * ERASE_BATCH_SIZE];
+        let zeroes = [0; MAX_BUCKET_SIZE * ERASE_BATCH_SIZE];
         let mut offset = size_of::<StoreHeader>();
-        let mut buckets = BUCKETS;
+        let mut buckets = self.buckets;
         while buckets > 0 {
             let batch = usize::min(buckets, ERASE_BATCH_SIZE);
             buckets -= batch;
 
-            let chunk = batch * size_of::<RawBucket>();
+            let chunk = batch * self.layout.bucket_size();
             self.adapter
                 .write(offset, &zeroes[..chunk])
                 .map_err(Error::AdapterError)?;
             offset += chunk;
         }
 
-        let header = StoreHeader::new()
-            .with_magic(self.cfg.magic)
-            .with_nonce(self.cfg.nonce)
-            .with_buckets(BUCKETS as u16);
-
-        self.adapter
-            .write(0, &header.into_bytes())
-            .map_err(Error::AdapterError)?;
-
-        Ok(())
+        self.alloc_loaded = false;
+        self.tombstones = true;
+        self.record_kinds = true;
+        self.write_header()
     }
 
     pub fn alloc(
@@ -125,7 +423,7 @@ where
         val_len: usize,
         fill_with: Option<u8>,
     ) -> Result<Bucket, Error<E>> {
-        let bucket = self.alloc_bucket(key, val_len)?;
+        let bucket = self.alloc_bucket(key, val_len, WriteCondition::Always, false)?;
         if let Some(fill_with) = fill_with {
             self.erase_bucket_content(&bucket, fill_with)?;
         }
@@ -135,21 +433,44 @@ where
         Ok(bucket)
     }
 
-    pub fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
-        let bucket = self.alloc_bucket(key, val.len())?;
-
+    pub(crate) fn alloc_compressed(
+        &mut self,
+        key: &[u8],
+        val_len: usize,
+    ) -> Result<Bucket, Error<E>> {
+        if !self.record_kinds {
+            return Err(Error::LegacyFormat);
+        }
+        let bucket = self.alloc_bucket(key, val_len, WriteCondition::Always, true)?;
         self.adapter
             .write(bucket.address(), key)
             .map_err(Error::AdapterError)?;
-
-        self.adapter
-            .write(bucket.address() + key.len(), val)
-            .map_err(Error::AdapterError)?;
         Ok(bucket)
     }
 
+    pub fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        self.write_record(key, val, WriteCondition::Always)
+    }
+
+    pub fn insert_if_absent(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        self.write_record(key, val, WriteCondition::Absent)
+    }
+
+    pub fn replace_if_exists(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
+        self.write_record(key, val, WriteCondition::Present)
+    }
+
+    pub fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: &[u8],
+        val: &[u8],
+    ) -> Result<Bucket, Error<E>> {
+        self.write_record(key, val, WriteCondition::Matches(expected))
+    }
+
     pub fn append(&mut self, key: &[u8], val: &[u8]) -> Result<Bucket, Error<E>> {
-        if SLOTS == 0 {
+        if self.is_read_only() {
             return Err(Error::ReadOnlyStore);
         }
 
@@ -166,6 +487,30 @@ where
         self.patch_value(bucket, offset, patch)
     }
 
+    pub fn truncate(&mut self, key: &[u8], new_len: usize) -> Result<Bucket, Error<E>> {
+        let bucket = self.lookup(key)?;
+        if new_len > bucket.val_len() {
+            return Err(Error::InvalidValueLength);
+        }
+        self.truncate_value(bucket, new_len)
+    }
+
+    pub fn resize(
+        &mut self,
+        key: &[u8],
+        new_len: usize,
+        fill_with: u8,
+    ) -> Result<Bucket, Error<E>> {
+        let bucket = self.lookup(key)?;
+        let val_len = bucket.val_len();
+        if new_len <= val_len {
+            return self.truncate_value(bucket, new_len);
+        }
+        self.grow_value(bucket, new_len, |store, val_addr| {
+            store.fill_bytes(val_addr + val_len, new_len - val_len, fill_with)
+        })
+    }
+
     pub fn load(&mut self, key: &[u8], buf: &mut [u8]) -> Result<Bucket, Error<E>> {
         self.load_at(key, buf, 0)
     }
@@ -187,12 +532,15 @@ where
         buf: &mut [u8],
         offset: usize,
     ) -> Result<Bucket, Error<E>> {
-        if offset + buf.len() > MAX_VALUE_LEN {
+        if offset > self.layout.max_val_len() {
             return Err(Error::ValueOverflow);
         }
         let bucket = self.lookup(key)?;
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
+        }
         let addr = bucket.address() + bucket.key_len() + offset;
-        let size = usize::min(buf.len(), bucket.val_len());
+        let size = usize::min(buf.len(), bucket.val_len().saturating_sub(offset));
         self.adapter
             .read(addr, &mut buf[..size])
             .map_err(Error::AdapterError)?;
@@ -203,11 +551,8 @@ where
         match self.lookup(key) {
             Ok(bucket) => {
                 self.erase_bucket_content(&bucket, fill_with)?;
-                let addr = size_of::<StoreHeader>() + size_of::<RawBucket>() * bucket.index();
-                self.adapter
-                    .write(addr, &RawBucket::new().into_bytes())
-                    .map_err(Error::AdapterError)?;
-                if SLOTS > 0 {
+                self.clear_bucket(bucket.index())?;
+                if !self.is_read_only() {
                     self.get_alloc()?
                         .free(bucket.address(), bucket.record_len());
                 }
@@ -221,32 +566,135 @@ where
     pub fn remove(&mut self, key: &[u8]) -> Result<(), Error<E>> {
         match self.lookup(key) {
             Ok(bucket) => {
-                let addr = size_of::<StoreHeader>() + size_of::<RawBucket>() * bucket.index();
-                self.adapter
-                    .write(addr, &RawBucket::new().into_bytes())
-                    .map_err(Error::AdapterError)?;
-                if SLOTS > 0 {
-                    self.get_alloc()?
-                        .free(bucket.address(), bucket.record_len());
-                }
-                Ok(())
+                self.clear_bucket(bucket.index())?;
+                self.release(bucket.address(), bucket.record_len())
             }
             Err(Error::KeyNotFound) => Ok(()),
             Err(err) => Err(err),
         }
     }
 
-    pub fn keys(&mut self) -> KeysIterator<'_, '_, A, BUCKETS, SLOTS> {
+    pub fn remove_prefix(&mut self, prefix: &[u8]) -> Result<usize, Error<E>> {
+        self.clear_prefix(prefix, None)
+    }
+
+    pub fn erase_prefix(&mut self, prefix: &[u8], fill_with: u8) -> Result<usize, Error<E>> {
+        self.clear_prefix(prefix, Some(fill_with))
+    }
+
+    pub fn scrub_free_space(&mut self) -> Result<usize, Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+
+        let fill_with = self.cfg.scrub.unwrap_or_default();
+        let mut scrubbed = 0;
+        self.sweep_free_ranges(|store, addr, len| {
+            scrubbed += len;
+            store.fill_bytes(addr, len, fill_with)
+        })?;
+        Ok(scrubbed)
+    }
+
+    pub fn rename(&mut self, old_key: &[u8], new_key: &[u8]) -> Result<Bucket, Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+        if new_key.is_empty() {
+            return Err(Error::InvalidKey);
+        }
+        if new_key.len() > self.layout.max_key_len() {
+            return Err(Error::KeyOverflow);
+        }
+
+        let bucket = self.lookup(old_key)?;
+        if old_key == new_key {
+            return Ok(bucket);
+        }
+        if self.exists(new_key)? {
+            return Err(Error::KeyExists);
+        }
+
+        let (old_addr, old_len) = (bucket.address(), bucket.record_len());
+        let val_addr = bucket.val_address();
+        let val_len = bucket.val_len();
+        let rebind_addr = match val_addr.checked_sub(new_key.len()) {
+            Some(addr) if addr >= old_addr => Some(addr),
+            Some(addr) if addr >= self.data_start() => {
+                self.alloc_space(old_addr - addr, Some(addr), None)?
+            }
+            _ => None,
+        };
+
+        let mut raw = RawBucket::new();
+        raw.set_key_len(new_key.len() as u16);
+        raw.set_val_len(val_len as u32);
+        raw.set_compressed(bucket.is_compressed());
+
+        let Some(addr) = rebind_addr else {
+            let addr = self
+                .alloc_space(new_key.len() + val_len, None, None)?
+                .ok_or(Error::StoreOverflow)?;
+            self.adapter
+                .write(addr, new_key)
+                .map_err(Error::AdapterError)?;
+            self.copy_bytes(val_addr, addr + new_key.len(), val_len)?;
+
+            raw.set_address(addr as u32);
+            if let Err(err) = self.rebind_bucket(&bucket, old_key, new_key, raw) {
+                self.alloc.free(addr, new_key.len() + val_len);
+                return Err(err);
+            }
+            self.release(old_addr, old_len)?;
+            return self.lookup(new_key);
+        };
+
+        raw.set_address(addr as u32);
+        if let Err(err) = self.rebind_bucket(&bucket, old_key, new_key, raw) {
+            if addr < old_addr {
+                self.alloc.free(addr, old_addr - addr);
+            }
+            return Err(err);
+        }
+        self.adapter
+            .write(addr, new_key)
+            .map_err(Error::AdapterError)?;
+        if addr > old_addr {
+            if let Some(fill_with) = self.cfg.scrub {
+                self.fill_bytes(old_addr, addr - old_addr, fill_with)?;
+            }
+            let alloc = self.get_alloc()?;
+            alloc.free(old_addr, old_len);
+            alloc.extend(addr, new_key.len() + val_len);
+        }
+
+        self.lookup(new_key)
+    }
+
+    pub fn keys(&mut self) -> KeysIterator<'_, '_, A, C> {
         KeysIterator::new(self)
     }
 
-    pub fn keys_with_prefix<'a>(
-        &mut self,
-        pat: &'a [u8],
-    ) -> KeysIterator<'_, 'a, A, BUCKETS, SLOTS> {
+    pub fn keys_with_prefix<'a>(&mut self, pat: &'a [u8]) -> KeysIterator<'_, 'a, A, C> {
         KeysIterator::with_prefix(self, pat)
     }
 
+    pub fn namespace<'a>(&'a mut self, prefix: &'a [u8]) -> Namespace<'a, A, C> {
+        Namespace::new(self, prefix)
+    }
+
+    pub fn encrypted<'a, K: Cipher>(
+        &'a mut self,
+        cipher: &'a K,
+        buf: &'a mut [u8],
+    ) -> Encrypted<'a, A, C, K> {
+        Encrypted::new(self, cipher, buf)
+    }
+
+    pub fn compressed<'a>(&'a mut self, buf: &'a mut [u8]) -> Compressed<'a, A, C> {
+        Compressed::new(self, buf)
+    }
+
     pub fn exists(&mut self, key: &[u8]) -> Result<bool, Error<E>> {
         match self.lookup(key) {
             Ok(_) => Ok(true),
@@ -255,16 +703,60 @@ where
         }
     }
 
+    pub fn stats(&mut self) -> Result<StoreStats, Error<E>> {
+        let mut stats = StoreStats {
+            buckets: self.buckets,
+            slots: self.alloc.capacity(),
+            data_size: self.data_size,
+            ..Default::default()
+        };
+
+        for index in 0..self.buckets {
+            let raw = self.load_bucket(index)?;
+            let key_len = raw.key_len() as usize;
+            if key_len == 0 {
+                continue;
+            }
+
+            self.adapter
+                .read(raw.address() as Address, &mut self.scratch[..key_len])
+                .map_err(Error::AdapterError)?;
+            let probe = self.probe(self.buckets, &self.scratch[..key_len]);
+            let distance = probe.take_while(|hop| *hop != index).count() + 1;
+
+            stats.used_buckets += 1;
+            stats.bytes_used += key_len + raw.val_len() as usize;
+            stats.max_probe_distance = usize::max(stats.max_probe_distance, distance);
+        }
+
+        if !self.is_read_only() {
+            self.get_alloc()?;
+            self.reclaim_space(None)?;
+            let alloc = &self.alloc;
+            stats.bytes_free = alloc.free_space();
+            stats.largest_free = alloc.largest_slot();
+            stats.used_slots = alloc.slots_used();
+        }
+
+        Ok(stats)
+    }
+
     pub fn lookup(&mut self, key: &[u8]) -> Result<Bucket, Error<E>> {
-        if key.len() > MAX_KEY_LEN {
+        if key.len() > self.layout.max_key_len() {
             return Err(Error::KeyOverflow);
         }
+        if key.is_empty() {
+            return Err(Error::KeyNotFound);
+        }
 
-        let hopper = Grasshopper::<BUCKETS>::new(self.cfg.max_hops, self.cfg.nonce, key);
-        let hash = hopper.hash();
+        let probe = self.probe(self.cfg.max_hops, key);
+        let hash = probe.tag();
 
-        for index in hopper {
+        for (distance, index) in probe.enumerate() {
             let raw = self.load_bucket(index)?;
+            if self.chain_ends(&raw, index, distance) {
+                break;
+            }
             if raw.hash() != hash || raw.key_len() as usize != key.len() {
                 continue;
             }
@@ -284,224 +776,785 @@ where
     }
 
     pub(crate) fn load_bucket(&mut self, bucket_index: usize) -> Result<RawBucket, Error<E>> {
-        let offset = size_of::<StoreHeader>() + size_of::<RawBucket>() * bucket_index;
-        let mut scratch = [0; size_of::<RawBucket>()];
+        self.read_raw_bucket(self.bucket_offset(bucket_index))
+    }
+
+    pub(crate) fn write_bucket(
+        &mut self,
+        bucket_index: usize,
+        raw: RawBucket,
+    ) -> Result<(), Error<E>> {
+        self.write_raw_bucket(self.bucket_offset(bucket_index), raw)
+    }
+
+    pub(crate) fn read_raw_bucket(&mut self, offset: Address) -> Result<RawBucket, Error<E>> {
+        let layout = self.layout;
+        let mut scratch = [0; MAX_BUCKET_SIZE];
         self.adapter
-            .read(offset, &mut scratch)
+            .read(offset, &mut scratch[..layout.bucket_size()])
             .map_err(Error::AdapterError)?;
-        Ok(RawBucket::from_bytes(scratch))
+        Ok(RawBucket::from_bytes(layout, self.record_kinds, &scratch))
+    }
+
+    pub(crate) fn write_raw_bucket(
+        &mut self,
+        offset: Address,
+        raw: RawBucket,
+    ) -> Result<(), Error<E>> {
+        let layout = self.layout;
+        self.adapter
+            .write(offset, &raw.into_bytes(layout)[..layout.bucket_size()])
+            .map_err(Error::AdapterError)
+    }
+
+    fn write_record(
+        &mut self,
+        key: &[u8],
+        val: &[u8],
+        condition: WriteCondition,
+    ) -> Result<Bucket, Error<E>> {
+        let bucket = self.alloc_bucket(key, val.len(), condition, false)?;
+
+        self.adapter
+            .write(bucket.address(), key)
+            .map_err(Error::AdapterError)?;
+
+        self.adapter
+            .write(bucket.address() + key.len(), val)
+            .map_err(Error::AdapterError)?;
+        Ok(bucket)
     }
 
-    fn alloc_bucket(&mut self, key: &[u8], val_len: usize) -> Result<Bucket, Error<E>> {
-        if SLOTS == 0 {
+    fn alloc_bucket(
+        &mut self,
+        key: &[u8],
+        val_len: usize,
+        condition: WriteCondition,
+        compressed: bool,
+    ) -> Result<Bucket, Error<E>> {
+        if self.is_read_only() {
             return Err(Error::ReadOnlyStore);
         }
-        if val_len > MAX_VALUE_LEN {
+        if val_len > self.layout.max_val_len() {
             return Err(Error::ValueOverflow);
         }
         let key_len = key.len();
-        if key.len() > MAX_KEY_LEN {
+        if key.len() > self.layout.max_key_len() {
             return Err(Error::KeyOverflow);
         }
+        if key.is_empty() {
+            return Err(Error::InvalidKey);
+        }
 
-        let hopper: Grasshopper<BUCKETS> = Grasshopper::new(BUCKETS, self.cfg.nonce, key);
-        let hash = hopper.hash();
-        let mut free_bucket: Option<Bucket> = None;
+        let probe = self.probe(self.cfg.max_hops, key);
+        let hash = probe.tag();
+        let in_place = matches!(self.strategy, ProbeStrategy::Rehash | ProbeStrategy::Linear);
+        let mut existing: Option<Bucket> = None;
+        let mut vacant = None;
 
-        for index in hopper {
-            let mut raw = self.load_bucket(index)?;
-            if raw.key_len() > 0 {
-                if raw.hash() != hash || raw.key_len() as usize != key_len {
-                    continue;
-                }
+        for (distance, index) in probe.enumerate() {
+            let raw = self.load_bucket(index)?;
+            if in_place && vacant.is_none() && raw.key_len() == 0 {
+                vacant = Some(index);
+            }
+            if self.chain_ends(&raw, index, distance) {
+                break;
+            }
+            if raw.hash() != hash || raw.key_len() as usize != key_len {
+                continue;
+            }
 
-                self.adapter
-                    .read(raw.address() as Address, &mut self.scratch[..key_len])
-                    .map_err(Error::AdapterError)?;
-                if key != &self.scratch[..key_len] {
-                    continue;
+            self.adapter
+                .read(raw.address() as Address, &mut self.scratch[..key_len])
+                .map_err(Error::AdapterError)?;
+            if key != &self.scratch[..key_len] {
+                continue;
+            }
+
+            existing = Some(Bucket { index, raw });
+            break;
+        }
+
+        let mut released = None;
+        let (mut bucket, placement) = match existing {
+            Some(bucket) => {
+                if let WriteCondition::Absent = condition {
+                    return Err(Error::KeyExists);
+                }
+                if let WriteCondition::Matches(expected) = condition {
+                    if !self.value_matches(&bucket, expected)? {
+                        return Err(Error::ValueMismatch);
+                    }
                 }
 
-                let bucket = Bucket { index, raw };
                 self.get_alloc()?
                     .free(bucket.address(), bucket.record_len());
-                free_bucket = Some(bucket);
-                break;
-            } else {
-                raw.set_hash(hash);
-                raw.set_key_len(key_len as u8);
-
-                free_bucket = Some(Bucket { index, raw });
-                break;
+                released = Some((bucket.address(), bucket.record_len()));
+                (bucket, None)
             }
-        }
+            None => {
+                if matches!(
+                    condition,
+                    WriteCondition::Present | WriteCondition::Matches(_)
+                ) {
+                    return Err(Error::KeyNotFound);
+                }
 
-        let mut bucket = free_bucket.ok_or(Error::IndexOverflow)?;
-        let addr = match self.get_alloc()?.alloc(key_len + val_len, None) {
-            Some(addr) => addr,
-            None => return Err(Error::StoreOverflow),
+                let placement = match vacant {
+                    Some(index) => Placement::Empty(index),
+                    None if in_place => return Err(Error::IndexOverflow),
+                    None => self.plan_placement(self.probe(self.cfg.max_hops, key))?,
+                };
+                let bucket = Bucket {
+                    index: placement.index(),
+                    raw: RawBucket::new(),
+                };
+                (bucket, Some(placement))
+            }
         };
 
-        bucket.raw.set_key_len(key_len as u8);
+        let addr = self
+            .alloc_space(key_len + val_len, None, released)?
+            .ok_or(Error::StoreOverflow)?;
+
+        bucket.raw.set_hash(hash);
+        bucket.raw.set_key_len(key_len as u16);
         bucket.raw.set_address(addr as u32);
-        bucket.raw.set_val_len(val_len as u16);
+        bucket.raw.set_val_len(val_len as u32);
+        bucket.raw.set_compressed(compressed);
 
-        self.adapter
-            .write(
-                size_of::<StoreHeader>() + size_of::<RawBucket>() * bucket.index(),
-                &bucket.raw.clone().into_bytes(),
-            )
-            .map_err(Error::AdapterError)?;
+        if let Some(placement) = placement {
+            self.make_place(&placement)?;
+        }
+        self.write_bucket(bucket.index(), bucket.raw.clone())?;
+
+        if let (Some(fill_with), Some((old_addr, old_len))) = (self.cfg.scrub, released) {
+            let old_end = old_addr + old_len;
+            let new_end = addr + key_len + val_len;
+            if addr > old_addr {
+                self.fill_bytes(old_addr, usize::min(addr, old_end) - old_addr, fill_with)?;
+            }
+            if old_end > new_end {
+                let start = usize::max(new_end, old_addr);
+                self.fill_bytes(start, old_end - start, fill_with)?;
+            }
+        }
 
         Ok(bucket)
     }
 
+    fn clear_prefix(&mut self, prefix: &[u8], fill_with: Option<u8>) -> Result<usize, Error<E>> {
+        if prefix.len() > self.layout.max_key_len() {
+            return Err(Error::KeyOverflow);
+        }
+
+        let mut removed = 0;
+        let mut index = 0;
+        while index < self.buckets {
+            let raw = self.load_bucket(index)?;
+            let key_len = raw.key_len() as usize;
+            if key_len == 0 || key_len < prefix.len() {
+                index += 1;
+                continue;
+            }
+
+            self.adapter
+                .read(raw.address() as Address, &mut self.scratch[..prefix.len()])
+                .map_err(Error::AdapterError)?;
+            if prefix != &self.scratch[..prefix.len()] {
+                index += 1;
+                continue;
+            }
+
+            let bucket = Bucket { index, raw };
+            self.clear_bucket(index)?;
+            match fill_with {
+                Some(fill_with) => {
+                    self.erase_bucket_content(&bucket, fill_with)?;
+                    if !self.is_read_only() {
+                        self.get_alloc()?
+                            .free(bucket.address(), bucket.record_len());
+                    }
+                }
+                None => self.release(bucket.address(), bucket.record_len())?,
+            }
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    fn rebind_bucket(
+        &mut self,
+        old: &Bucket,
+        old_key: &[u8],
+        new_key: &[u8],
+        raw: RawBucket,
+    ) -> Result<(), Error<E>> {
+        match self.place_bucket(new_key, raw.clone()) {
+            Ok(bucket) => {
+                let index = self.locate_bucket(old_key, old.address(), bucket.index())?;
+                self.clear_bucket(index)
+            }
+            Err(Error::IndexOverflow) => {
+                self.clear_bucket(old.index())?;
+                if let Err(err) = self.place_bucket(new_key, raw) {
+                    self.place_bucket(old_key, old.raw.clone())?;
+                    return Err(err);
+                }
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn locate_bucket(&mut self, key: &[u8], addr: Address, skip: usize) -> Result<usize, Error<E>> {
+        let probe = self.probe(self.cfg.max_hops, key);
+        let hash = probe.tag();
+        for (distance, index) in probe.enumerate() {
+            let raw = self.load_bucket(index)?;
+            if self.chain_ends(&raw, index, distance) {
+                break;
+            }
+            if index != skip
+                && raw.hash() == hash
+                && raw.key_len() != 0
+                && raw.address() as Address == addr
+            {
+                return Ok(index);
+            }
+        }
+        Err(Error::KeyNotFound)
+    }
+
+    fn place_bucket(&mut self, key: &[u8], raw: RawBucket) -> Result<Bucket, Error<E>> {
+        let probe = self.probe(self.cfg.max_hops, key);
+        let mut raw = raw;
+        raw.set_hash(probe.tag());
+
+        let placement = self.plan_placement(probe)?;
+        self.make_place(&placement)?;
+        self.write_bucket(placement.index(), raw.clone())?;
+        Ok(Bucket {
+            index: placement.index(),
+            raw,
+        })
+    }
+
+    fn value_matches(&mut self, bucket: &Bucket, expected: &[u8]) -> Result<bool, Error<E>> {
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
+        }
+        if bucket.val_len() != expected.len() {
+            return Ok(false);
+        }
+
+        let mut offset = 0;
+        while offset < expected.len() {
+            let chunk = usize::min(MAX_KEY_LEN, expected.len() - offset);
+            self.adapter
+                .read(bucket.val_address() + offset, &mut self.scratch[..chunk])
+                .map_err(Error::AdapterError)?;
+            if self.scratch[..chunk] != expected[offset..][..chunk] {
+                return Ok(false);
+            }
+            offset += chunk;
+        }
+
+        Ok(true)
+    }
+
+    pub(crate) fn copy_bytes(
+        &mut self,
+        src: Address,
+        dst: Address,
+        len: usize,
+    ) -> Result<(), Error<E>> {
+        let backward = dst > src && dst < src + len;
+        let mut copied = 0;
+        while copied < len {
+            let chunk = usize::min(MAX_KEY_LEN, len - copied);
+            let offset = if backward {
+                len - copied - chunk
+            } else {
+                copied
+            };
+            self.adapter
+                .read(src + offset, &mut self.scratch[..chunk])
+                .map_err(Error::AdapterError)?;
+            self.adapter
+                .write(dst + offset, &self.scratch[..chunk])
+                .map_err(Error::AdapterError)?;
+            copied += chunk;
+        }
+
+        Ok(())
+    }
+
     fn erase_bucket_content(&mut self, bucket: &Bucket, fill_with: u8) -> Result<(), Error<E>> {
+        self.fill_bytes(bucket.address(), bucket.record_len(), fill_with)
+    }
+
+    pub(crate) fn fill_bytes(
+        &mut self,
+        addr: Address,
+        len: usize,
+        fill_with: u8,
+    ) -> Result<(), Error<E>> {
         const FILLER_LEN: usize = 8;
         let filler: [u8; FILLER_LEN] = [fill_with; FILLER_LEN];
 
         let mut offset = 0;
-        let mut chunk = usize::min(FILLER_LEN, bucket.record_len());
+        let mut chunk = usize::min(FILLER_LEN, len);
         while chunk > 0 {
             self.adapter
-                .write(bucket.address() + offset, &filler[..chunk])
+                .write(addr + offset, &filler[..chunk])
                 .map_err(Error::AdapterError)?;
             offset += chunk;
-            chunk = usize::min(FILLER_LEN, bucket.record_len() - offset);
+            chunk = usize::min(FILLER_LEN, len - offset);
         }
 
         Ok(())
     }
 
-    fn patch_value(
+    pub(crate) fn patch_value(
         &mut self,
         bucket: Bucket,
         offset: usize,
         patch: &[u8],
     ) -> Result<Bucket, Error<E>> {
         let new_val_len = usize::max(offset + patch.len(), bucket.val_len());
-        if new_val_len > MAX_VALUE_LEN {
+        self.grow_value(bucket, new_val_len, |store, val_addr| {
+            let addr = val_addr + offset;
+            if addr + patch.len() > store.adapter.max_address() {
+                return Err(Error::StoreOverflow);
+            }
+            store
+                .adapter
+                .write(addr, patch)
+                .map_err(Error::AdapterError)
+        })
+    }
+
+    fn grow_value<F>(
+        &mut self,
+        bucket: Bucket,
+        new_val_len: usize,
+        write: F,
+    ) -> Result<Bucket, Error<E>>
+    where
+        F: FnOnce(&mut Self, Address) -> Result<(), Error<E>>,
+    {
+        if new_val_len > self.layout.max_val_len() {
             return Err(Error::ValueOverflow);
         }
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
+        }
+        if new_val_len <= bucket.val_len() {
+            write(self, bucket.val_address())?;
+            return Ok(bucket);
+        }
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
 
         let mut bucket = bucket;
-        if new_val_len > bucket.val_len() {
-            self.get_alloc()?
-                .alloc(
-                    new_val_len - bucket.val_len(),
-                    Some(bucket.address() + bucket.record_len()),
-                )
-                .ok_or(Error::ValueOverflow)?;
-            bucket.raw.set_val_len(new_val_len as u16);
-
-            let addr = size_of::<StoreHeader>() + size_of::<RawBucket>() * bucket.index();
-            self.adapter
-                .write(addr, &bucket.raw.clone().into_bytes())
-                .map_err(Error::AdapterError)?;
+        let (old_addr, old_len) = (bucket.address(), bucket.record_len());
+        let end = old_addr + old_len;
+        let addr = match self.extend_space(end, new_val_len - bucket.val_len())? {
+            Some(_) => old_addr,
+            None => {
+                let addr = self
+                    .alloc_space(bucket.key_len() + new_val_len, None, None)?
+                    .ok_or(Error::StoreOverflow)?;
+                self.copy_bytes(old_addr, addr, old_len)?;
+                addr
+            }
+        };
+
+        write(self, addr + bucket.key_len())?;
+        bucket.raw.set_address(addr as u32);
+        bucket.raw.set_val_len(new_val_len as u32);
+        self.write_bucket(bucket.index(), bucket.raw.clone())?;
+        if addr != old_addr {
+            self.release(old_addr, old_len)?;
         }
 
-        let addr = bucket.address() + bucket.key_len() + offset;
-        if addr + patch.len() > self.adapter.max_address() {
-            return Err(Error::StoreOverflow);
+        Ok(bucket)
+    }
+
+    fn truncate_value(&mut self, bucket: Bucket, new_len: usize) -> Result<Bucket, Error<E>> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnlyStore);
+        }
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
         }
-        self.adapter
-            .write(addr, patch)
-            .map_err(Error::AdapterError)?;
+
+        let mut bucket = bucket;
+        let tail = bucket.val_len() - new_len;
+        if tail == 0 {
+            return Ok(bucket);
+        }
+
+        bucket.raw.set_val_len(new_len as u32);
+        self.write_bucket(bucket.index(), bucket.raw.clone())?;
+        self.release(bucket.address() + bucket.record_len(), tail)?;
 
         Ok(bucket)
     }
 
-    fn get_alloc(&mut self) -> Result<&mut alloc::Alloc<SLOTS>, Error<E>> {
-        if self.alloc.is_none() {
-            self.alloc = Some(self.load_index()?);
+    fn release(&mut self, addr: Address, size: usize) -> Result<(), Error<E>> {
+        if let Some(fill_with) = self.cfg.scrub {
+            self.fill_bytes(addr, size, fill_with)?;
+        }
+        if !self.is_read_only() {
+            self.get_alloc()?.free(addr, size);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn alloc_space(
+        &mut self,
+        size: usize,
+        addr: Option<Address>,
+        released: Option<(Address, usize)>,
+    ) -> Result<Option<Address>, Error<E>> {
+        let mut res = self.get_alloc()?.alloc(size, addr);
+        if res.is_none() && self.reclaim_space(released)? {
+            res = self.alloc.alloc(size, addr);
+        }
+        Ok(res)
+    }
+
+    fn extend_space(&mut self, addr: Address, size: usize) -> Result<Option<Address>, Error<E>> {
+        let mut res = self.get_alloc()?.extend(addr, size);
+        if res.is_none() && self.reclaim_space(None)? {
+            res = self.alloc.extend(addr, size);
         }
-        Ok(self.alloc.as_mut().unwrap())
+        Ok(res)
     }
 
-    fn load_index(&mut self) -> Result<alloc::Alloc<SLOTS>, Error<E>> {
+    fn get_alloc(&mut self) -> Result<&mut C::Alloc, Error<E>> {
+        if !self.alloc_loaded {
+            self.load_index()?;
+            self.alloc_loaded = true;
+        }
+        Ok(&mut self.alloc)
+    }
+
+    fn reclaim_space(&mut self, released: Option<(Address, usize)>) -> Result<bool, Error<E>> {
+        if !self.alloc.has_untracked_space() {
+            return Ok(false);
+        }
+
+        self.load_index()?;
+        if let Some((addr, size)) = released {
+            self.alloc.free(addr, size);
+        }
+        Ok(true)
+    }
+
+    fn load_index(&mut self) -> Result<(), Error<E>> {
         const BUCKET_BATCH_SIZE: usize = 32;
-        const BUCKET_SIZE: usize = size_of::<RawBucket>();
 
-        let mut buf = [0; BUCKET_SIZE * BUCKET_BATCH_SIZE];
+        let layout = self.layout;
+        let bucket_size = layout.bucket_size();
+        let mut buf = [0; MAX_BUCKET_SIZE * BUCKET_BATCH_SIZE];
         let mut offset = size_of::<StoreHeader>();
-        let mut buckets = BUCKETS;
-        let mut alloc = Alloc::<SLOTS>::new(
-            self.cfg.alloc_strategy,
-            Self::DATA_START,
-            self.adapter.max_address() - Self::DATA_START,
-        );
+        let mut buckets = self.buckets;
+        self.alloc.reset(self.data_start(), self.data_size);
 
         while buckets > 0 {
             let batch = usize::min(buckets, BUCKET_BATCH_SIZE);
-            let chunk = batch * BUCKET_SIZE;
+            let chunk = batch * bucket_size;
             self.adapter
                 .read(offset, &mut buf[..chunk])
                 .map_err(Error::AdapterError)?;
 
             for bucket_idx in 0..batch {
-                let mut scratch = [0; BUCKET_SIZE];
-                scratch.copy_from_slice(&buf[bucket_idx * BUCKET_SIZE..][..BUCKET_SIZE]);
-
-                let raw = RawBucket::from_bytes(scratch);
+                let raw = RawBucket::from_bytes(
+                    layout,
+                    self.record_kinds,
+                    &buf[bucket_idx * bucket_size..],
+                );
                 if raw.key_len() == 0 {
                     continue;
                 }
                 let addr = raw.address() as Address;
                 let size = raw.key_len() as usize + raw.val_len() as usize;
-                alloc.alloc(size, Some(addr)).ok_or(Error::StoreOverflow)?;
+                if self.alloc.extend(addr, size).is_none() && !self.alloc.has_untracked_space() {
+                    return Err(Error::StoreOverflow);
+                }
             }
             offset += chunk;
             buckets -= batch;
         }
 
-        Ok(alloc)
+        if self.alloc.has_untracked_space() {
+            self.sweep_index()?;
+        }
+        Ok(())
+    }
+
+    fn sweep_index(&mut self) -> Result<(), Error<E>> {
+        self.alloc.reset(self.data_start(), 0);
+        self.sweep_free_ranges(|store, addr, len| {
+            store.alloc.free(addr, len);
+            Ok(())
+        })
+    }
+
+    fn sweep_free_ranges<F>(&mut self, mut visit: F) -> Result<(), Error<E>>
+    where
+        F: FnMut(&mut Self, Address, usize) -> Result<(), Error<E>>,
+    {
+        const SWEEP_BATCH_SIZE: usize = 32;
+
+        let mut cursor = self.data_start();
+        let data_end = cursor + self.data_size;
+        let mut last_start = None;
+
+        loop {
+            let mut batch = [(0, 0); SWEEP_BATCH_SIZE];
+            let mut len = 0;
+            for index in 0..self.buckets {
+                let raw = self.load_bucket(index)?;
+                if raw.key_len() == 0 {
+                    continue;
+                }
+                let start = raw.address() as Address;
+                if last_start.is_some_and(|last| start <= last)
+                    || (len == SWEEP_BATCH_SIZE && start >= batch[len - 1].0)
+                {
+                    continue;
+                }
+
+                let pos = batch[..len].partition_point(|(addr, _)| *addr < start);
+                if len < SWEEP_BATCH_SIZE {
+                    len += 1;
+                }
+                batch.copy_within(pos..len - 1, pos + 1);
+                batch[pos] = (
+                    start,
+                    start + raw.key_len() as usize + raw.val_len() as usize,
+                );
+            }
+
+            for (start, end) in &batch[..len] {
+                if *start > cursor {
+                    visit(self, cursor, start - cursor)?;
+                }
+                cursor = usize::max(cursor, *end);
+            }
+            if len < SWEEP_BATCH_SIZE {
+                break;
+            }
+            last_start = Some(batch[len - 1].0);
+        }
+
+        if data_end > cursor {
+            visit(self, cursor, data_end - cursor)?;
+        }
+        Ok(())
     }
 
-    fn load_header(adapter: &mut A, magic: u32, nonce: u16) -> Result<StoreHeader, Error<E>> {
+    pub(crate) fn write_header(&mut self) -> Result<(), Error<E>> {
+        let header = StoreHeader::new()
+            .with_magic(self.cfg.magic)
+            .with_nonce(self.cfg.nonce)
+            .with_buckets(self.buckets as u16)
+            .with_signature(FORMAT_SIGNATURE)
+            .with_version(FORMAT_VERSION)
+            .with_flags(
+                self.layout.flags()
+                    | self.hasher.id() << FLAG_HASHER_SHIFT
+                    | self.strategy.id() << FLAG_PROBE_SHIFT
+                    | if self.tombstones { FLAG_TOMBSTONES } else { 0 }
+                    | if self.nonce_slot { FLAG_NONCE_SLOT } else { 0 }
+                    | if self.record_kinds {
+                        FLAG_RECORD_KINDS
+                    } else {
+                        0
+                    },
+            )
+            .with_data_size(self.data_size as u32);
+
+        self.adapter
+            .write(0, &header.into_bytes())
+            .map_err(Error::AdapterError)
+    }
+
+    pub(crate) fn read_header(adapter: &mut A) -> Result<StoreHeader, Error<E>> {
         let mut buf = [0; size_of::<StoreHeader>()];
         adapter
             .read(0, &mut buf)
             .map_err(Error::AdapterError)
             .map(|_| StoreHeader::from_bytes(buf))
-            .and_then(|header| {
-                if header.magic() != magic {
-                    return Err(Error::StoreNotFound);
-                }
+    }
 
-                if header.nonce() != nonce {
-                    return Err(Error::InvalidNonce);
-                }
+    pub(crate) fn check_header(
+        header: &StoreHeader,
+        magic: u32,
+        nonce: u16,
+    ) -> Result<(), Error<E>> {
+        if header.magic() != magic {
+            return Err(Error::StoreNotFound);
+        }
 
-                if header.buckets() as usize != BUCKETS {
-                    return Err(Error::InvalidCapacity);
-                }
+        if header.nonce() != nonce {
+            return Err(Error::InvalidNonce);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn load_header(
+        adapter: &mut A,
+        magic: u32,
+        nonce: u16,
+    ) -> Result<StoreHeader, Error<E>> {
+        let header = Self::read_header(adapter)?;
+        Self::check_format(&header, magic)?;
+        Self::check_header(&header, magic, nonce)?;
+        Ok(header)
+    }
+
+    pub(crate) fn check_format(header: &StoreHeader, magic: u32) -> Result<(), Error<E>> {
+        if header.magic() != magic {
+            return Err(Error::StoreNotFound);
+        }
+
+        if header.signature() != FORMAT_SIGNATURE || header.version() == 0 {
+            return Err(Error::LegacyFormat);
+        }
+
+        if header.version() > FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        if header.hash_algorithm().is_none() || header.probe_strategy().is_none() {
+            return Err(Error::UnsupportedVersion);
+        }
 
-                Ok(header)
-            })
+        Ok(())
     }
 }
 
 #[cfg(feature = "serde")]
-impl<E, A, const BUCKETS: usize, const SLOTS: usize> KVStore<A, BUCKETS, SLOTS>
+impl<E, A, C> Store<A, C>
 where
     A: StoreAdapter<Error = E>,
+    C: Capacity,
 {
     pub fn insert_val<T: Serialize, const N: usize>(
         &mut self,
         id: &[u8],
         val: &T,
     ) -> Result<Bucket, crate::Error<E>> {
-        let val: Vec<u8, N> = to_vec(val).map_err(Error::SerializationError)?;
-        self.insert(id, &val)
+        self.insert_val_with::<_, T, N>(&Postcard, id, val)
     }
 
     pub fn load_val<T: DeserializeOwned, const N: usize>(
         &mut self,
         id: &[u8],
+    ) -> Result<T, crate::Error<E>> {
+        self.load_val_with::<_, T, N>(&Postcard, id)
+    }
+
+    pub fn insert_val_with<K: Codec, T: Serialize, const N: usize>(
+        &mut self,
+        codec: &K,
+        id: &[u8],
+        val: &T,
+    ) -> Result<Bucket, crate::Error<E>> {
+        let mut buf = [0; N];
+        let len = codec.encode(val, &mut buf)?;
+        self.insert(id, &buf[..len])
+    }
+
+    pub fn load_val_with<K: Codec, T: DeserializeOwned, const N: usize>(
+        &mut self,
+        codec: &K,
+        id: &[u8],
     ) -> Result<T, crate::Error<E>> {
         let mut buf = [0; N];
         let bucket = self.load(id, &mut buf)?;
-        let res = from_bytes(&buf[0..bucket.val_len()]).map_err(Error::SerializationError)?;
-        Ok(res)
+        if bucket.val_len() > N {
+            return Err(Error::ValueOverflow);
+        }
+        codec.decode(&mut buf[..bucket.val_len()])
+    }
+
+    pub fn load_val_in<'a, T: Deserialize<'a>>(
+        &mut self,
+        id: &[u8],
+        buf: &'a mut [u8],
+    ) -> Result<T, crate::Error<E>> {
+        self.load_val_in_with(&Postcard, id, buf)
+    }
+
+    pub fn load_val_in_with<'a, K: Codec, T: Deserialize<'a>>(
+        &mut self,
+        codec: &K,
+        id: &[u8],
+        buf: &'a mut [u8],
+    ) -> Result<T, crate::Error<E>> {
+        let bucket = self.load(id, buf)?;
+        if bucket.val_len() > buf.len() {
+            return Err(Error::ValueOverflow);
+        }
+        codec.decode(&mut buf[..bucket.val_len()])
+    }
+
+    pub fn load_val_stream<T: DeserializeOwned>(
+        &mut self,
+        id: &[u8],
+        scratch: &mut [u8],
+    ) -> Result<T, crate::Error<E>> {
+        let bucket = self.lookup(id)?;
+        if bucket.is_compressed() {
+            return Err(Error::CompressedValue);
+        }
+        let mut de = postcard::Deserializer::from_flavor(ValueReader::new(
+            &mut self.adapter,
+            &bucket,
+            scratch,
+        ));
+        let res = T::deserialize(&mut de);
+        match (res, de.finalize()) {
+            (_, Ok(Some(err))) => Err(Error::AdapterError(err)),
+            (Ok(val), _) => Ok(val),
+            (Err(err), _) => Err(Error::SerializationError(err)),
+        }
+    }
+
+    pub fn insert_val_stream<T: Serialize + ?Sized>(
+        &mut self,
+        id: &[u8],
+        val: &T,
+    ) -> Result<Bucket, crate::Error<E>> {
+        let size = postcard::serialize_with_flavor(val, postcard::ser_flavors::Size::default())
+            .map_err(Error::SerializationError)?;
+        let bucket = self.alloc(id, size, None)?;
+
+        let mut error = None;
+        let writer = ValueWriter::new(&mut self.adapter, &bucket, &mut error);
+        let res = postcard::serialize_with_flavor(val, writer);
+        match (res, error) {
+            (Ok(()), _) => Ok(bucket),
+            (Err(err), adapter_error) => {
+                self.remove(id)?;
+                match adapter_error {
+                    Some(err) => Err(Error::AdapterError(err)),
+                    None => Err(Error::SerializationError(err)),
+                }
+            }
+        }
+    }
+
+    pub fn encoded<'a, K: Codec>(&'a mut self, codec: &'a K) -> Encoded<'a, A, C, K> {
+        Encoded::new(self, codec)
     }
 }
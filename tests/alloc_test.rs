@@ -0,0 +1,116 @@
+use kvs::adapters::ram::*;
+use kvs::{Alloc, AllocStrategy, Allocator, KVStore, Slot, StoreConfig};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 2048;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 2;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_free_coalesces_both_sides() {
+    let mut alloc = Alloc::new([Slot::default(); 2], AllocStrategy::FirstFit, 0, 96);
+    let first = alloc.alloc(32, None).unwrap();
+    let second = alloc.alloc(32, None).unwrap();
+    let third = alloc.alloc(32, None).unwrap();
+    assert_eq!(alloc.free_space(), 0);
+
+    alloc.free(first, 32);
+    alloc.free(third, 32);
+    assert_eq!(alloc.slots_used(), 2);
+
+    alloc.free(second, 32);
+    assert_eq!(alloc.slots_used(), 1);
+    assert_eq!(alloc.largest_slot(), 96);
+    assert!(!alloc.has_untracked_space());
+}
+
+#[test]
+fn test_split_without_spare_slot() {
+    let mut alloc = Alloc::new([Slot::default(); 1], AllocStrategy::FirstFit, 0, 100);
+    assert_eq!(alloc.alloc(10, Some(20)), Some(20));
+    assert_eq!(alloc.free_space(), 70);
+    assert!(alloc.has_untracked_space());
+
+    let mut alloc = Alloc::new([Slot::default(); 1], AllocStrategy::FirstFit, 0, 100);
+    alloc.alloc(100, None).unwrap();
+    alloc.free(0, 10);
+    alloc.free(50, 30);
+    assert_eq!(alloc.largest_slot(), 30);
+    assert!(alloc.has_untracked_space());
+}
+
+#[test]
+fn test_reclaim_after_churn() {
+    let mut store = tiny::create_store();
+    let data_size = store.stats().unwrap().data_size;
+
+    for round in 0..8u8 {
+        for idx in 0..24u8 {
+            let len = 1 + (idx as usize * 7 + round as usize * 3) % 40;
+            store.insert(&[b'k', idx], &vec![round; len]).unwrap();
+        }
+        for idx in (round % 3..24).step_by(3) {
+            store.remove(&[b'k', idx]).unwrap();
+        }
+    }
+
+    let stats = store.stats().unwrap();
+    assert!(stats.used_buckets > 0);
+    assert!(stats.bytes_free <= data_size - stats.bytes_used);
+
+    for idx in 0..24u8 {
+        store.remove(&[b'k', idx]).unwrap();
+    }
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.used_buckets, 0);
+    assert_eq!(stats.bytes_free, data_size);
+    assert_eq!(stats.largest_free, data_size);
+
+    store.alloc(b"big", data_size - 3, None).unwrap();
+}
+
+#[test]
+fn test_reuse_untracked_space() {
+    let mut store = tiny::create_store();
+    let data_size = store.stats().unwrap().data_size;
+    let count = (data_size / 64) as u8;
+    for idx in 0..count {
+        store.insert(&[b'k', idx], &[idx; 62]).unwrap();
+    }
+    for idx in (0..count).step_by(2) {
+        store.remove(&[b'k', idx]).unwrap();
+    }
+    assert!(store.stats().unwrap().bytes_free < data_size / 2);
+
+    for idx in (0..count).step_by(2) {
+        store.insert(&[b'n', idx], &[idx; 62]).unwrap();
+    }
+    let mut buf = [0; 62];
+    for idx in 0..count {
+        let key = if idx % 2 == 0 { b'n' } else { b'k' };
+        assert_eq!(store.load_slice(&[key, idx], &mut buf).unwrap(), [idx; 62]);
+    }
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.bytes_free, data_size - stats.bytes_used);
+    assert_eq!(
+        store.insert(b"foo", &[0; 62]).unwrap_err(),
+        kvs::Error::StoreOverflow
+    );
+}
@@ -0,0 +1,130 @@
+use kvs::adapters::ram::*;
+use kvs::{AllocStrategy, Allocator, BlockAlloc, BlockKVStore, StoreConfig};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 4096;
+    pub const BUCKETS: usize = 64;
+    pub const BLOCK_SIZE: usize = 16;
+    pub const WORDS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = BlockKVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, BLOCK_SIZE, WORDS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_block_alloc() {
+    let mut alloc = BlockAlloc::new([0; 1], 8, AllocStrategy::FirstFit, 100, 100);
+    assert_eq!(alloc.block_size(), 8);
+    assert_eq!(alloc.blocks(), 13);
+    assert_eq!(alloc.free_space(), 100);
+    assert_eq!(alloc.slots_used(), 1);
+
+    let first = alloc.alloc(10, None).unwrap();
+    let second = alloc.alloc(3, None).unwrap();
+    let third = alloc.alloc(20, None).unwrap();
+    assert_eq!((first, second, third), (100, 116, 124));
+    assert_eq!(alloc.used_blocks(), 6);
+    assert_eq!(alloc.free_space(), 52);
+
+    alloc.free(second, 3);
+    assert_eq!(alloc.slots_used(), 2);
+    assert_eq!(alloc.largest_slot(), 52);
+
+    alloc.free(first, 10);
+    alloc.free(third, 20);
+    assert_eq!(alloc.slots_used(), 1);
+    assert_eq!(alloc.free_space(), 100);
+    assert_eq!(alloc.alloc(100, None), Some(100));
+    assert_eq!(alloc.alloc(1, None), None);
+}
+
+#[test]
+fn test_block_size_covers_region() {
+    let alloc = BlockAlloc::new([0; 2], 4, AllocStrategy::FirstFit, 0, 1000);
+    assert_eq!(alloc.block_size(), 16);
+    assert_eq!(alloc.blocks(), 63);
+    assert_eq!(alloc.free_space(), 1000);
+}
+
+#[test]
+fn test_block_store() {
+    let mut store = tiny::create_store();
+    assert_eq!(store.block_size(), tiny::BLOCK_SIZE);
+
+    for idx in 0..48u8 {
+        store.insert(&[b'k', idx], &[idx; 6]).unwrap();
+    }
+    for idx in (0..48u8).step_by(2) {
+        store.remove(&[b'k', idx]).unwrap();
+    }
+    store.append(&[b'k', 1], &[0xff; 8]).unwrap();
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.slots, tiny::WORDS * 32);
+    assert_eq!(stats.used_buckets, 24);
+    assert_eq!(stats.bytes_free, stats.data_size - 24 * tiny::BLOCK_SIZE);
+    assert_eq!(stats.used_slots, 25);
+
+    let mut store = tiny::Store::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    )
+    .unwrap();
+    assert_eq!(store.stats().unwrap(), stats);
+
+    let mut buf = [0; 16];
+    let val = store.load_slice(&[b'k', 1], &mut buf).unwrap();
+    assert_eq!(val[..6], [1; 6]);
+    assert_eq!(val[6..], [0xff; 8]);
+    for idx in (1..48u8).step_by(2) {
+        assert!(store.exists(&[b'k', idx]).unwrap());
+    }
+}
+
+#[test]
+fn test_block_store_fills_region() {
+    let mut store = tiny::create_store();
+    let data_size = store.stats().unwrap().data_size;
+    let count = data_size / (4 * tiny::BLOCK_SIZE);
+
+    for idx in 0..count {
+        store.insert(&(idx as u16).to_be_bytes(), &[0; 62]).unwrap();
+    }
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.bytes_free, data_size - count * 4 * tiny::BLOCK_SIZE);
+
+    let err = store.insert(b"foo", &[0; 61]).unwrap_err();
+    assert_eq!(err, kvs::Error::StoreOverflow);
+}
+
+#[test]
+fn test_rename_keeps_neighbour() {
+    let mut store = BlockKVStore::<MemoryAdapter<4096>, 64, 16, 8>::open(
+        MemoryAdapter::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        true,
+    )
+    .unwrap();
+    store.insert(b"aaaa", &[0x11; 12]).unwrap();
+    store.insert(b"bbbb", &[0x22; 12]).unwrap();
+
+    store.rename(b"bbbb", b"bbbbbb").unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"aaaa", &mut buf).unwrap(), &[0x11; 12]);
+    assert_eq!(store.load_slice(b"bbbbbb", &mut buf).unwrap(), &[0x22; 12]);
+    assert!(!store.exists(b"bbbb").unwrap());
+}
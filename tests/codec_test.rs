@@ -0,0 +1,194 @@
+#![cfg(feature = "serde")]
+
+use kvs::adapters::ram::*;
+use kvs::{Codec, KVStore, Postcard, StoreConfig};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct Credentials {
+    ssid: [u8; 4],
+    channel: u8,
+}
+
+const TEST_ENTRY: Credentials = Credentials {
+    ssid: *b"home",
+    channel: 6,
+};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+fn round_trip<K: Codec>(codec: &K, raw: &[u8]) {
+    let mut store = tiny::create_store();
+    store
+        .insert_val_with::<_, _, 32>(codec, b"num", &42u32)
+        .unwrap();
+    let mut buf = [0; 32];
+    assert_eq!(store.load_slice(b"num", &mut buf).unwrap(), raw);
+
+    let mut encoded = store.encoded(codec);
+    encoded.insert_val::<_, 32>(b"wifi", &TEST_ENTRY).unwrap();
+    let entry: Credentials = encoded.load_val::<_, 32>(b"wifi").unwrap();
+    assert_eq!(entry, TEST_ENTRY);
+    let num: u32 = encoded.load_val::<_, 32>(b"num").unwrap();
+    assert_eq!(num, 42);
+}
+
+#[test]
+fn test_postcard_codec() {
+    round_trip(&Postcard, &[42]);
+
+    let mut store = tiny::create_store();
+    store.insert_val::<_, 32>(b"wifi", &TEST_ENTRY).unwrap();
+    let entry: Credentials = store.load_val_with::<_, _, 32>(&Postcard, b"wifi").unwrap();
+    assert_eq!(entry, TEST_ENTRY);
+}
+
+#[test]
+fn test_encode_overflow() {
+    let mut store = tiny::create_store();
+    let res = store.insert_val_with::<_, _, 4>(&Postcard, b"wifi", &TEST_ENTRY);
+    assert!(matches!(res, Err(kvs::Error::SerializationError(_))));
+    assert!(!store.exists(b"wifi").unwrap());
+}
+
+#[test]
+fn test_decode_overflow() {
+    let mut store = tiny::create_store();
+    store.insert(b"num", &[42; 40]).unwrap();
+    let res = store.load_val_with::<_, u8, 32>(&Postcard, b"num");
+    assert_eq!(res.unwrap_err(), kvs::Error::ValueOverflow);
+    let res = store.encoded(&Postcard).load_val::<u8, 32>(b"num");
+    assert_eq!(res.unwrap_err(), kvs::Error::ValueOverflow);
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_codec() {
+    use kvs::Cbor;
+
+    round_trip(&Cbor, &[0x18, 0x2a]);
+
+    let mut store = tiny::create_store();
+    store.insert(b"bad", &[0xff, 0xff]).unwrap();
+    let res = store.load_val_with::<_, u32, 32>(&Cbor, b"bad");
+    assert_eq!(
+        res.unwrap_err(),
+        kvs::Error::CodecError(kvs::CodecError::Malformed)
+    );
+    let res = store.insert_val_with::<_, _, 4>(&Cbor, b"wifi", &TEST_ENTRY);
+    assert_eq!(
+        res.unwrap_err(),
+        kvs::Error::CodecError(kvs::CodecError::BufferOverflow)
+    );
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_wire_format() {
+    use kvs::Cbor;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    enum Mode {
+        On,
+        Level(u8),
+        Window { from: i16, to: Option<u8> },
+    }
+
+    fn check<T>(val: T, raw: &[u8])
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + PartialEq,
+    {
+        let mut store = tiny::create_store();
+        store
+            .insert_val_with::<_, _, 32>(&Cbor, b"val", &val)
+            .unwrap();
+        let mut buf = [0; 32];
+        assert_eq!(store.load_slice(b"val", &mut buf).unwrap(), raw);
+        let loaded: T = store.load_val_with::<_, _, 32>(&Cbor, b"val").unwrap();
+        assert_eq!(loaded, val);
+    }
+
+    check(-1i32, &[0x20]);
+    check(1_000_000u32, &[0x1a, 0x00, 0x0f, 0x42, 0x40]);
+    check('a', &[0x18, 0x61]);
+    check((true, ()), &[0x82, 0xf5, 0x80]);
+    check(Mode::On, b"\x62On");
+    check(Mode::Level(3), b"\xa1\x65Level\x03");
+    check(
+        Mode::Window { from: -2, to: None },
+        b"\xa1\x66Window\xa2\x64from\x21\x62to\xf6",
+    );
+    check(
+        TEST_ENTRY,
+        b"\xa2\x64ssid\x84\x18\x68\x18\x6f\x18\x6d\x18\x65\x67channel\x06",
+    );
+
+    let mut store = tiny::create_store();
+    store.insert(b"half", &[0xf9, 0xc4, 0x00]).unwrap();
+    let val: f32 = store.load_val_with::<_, _, 32>(&Cbor, b"half").unwrap();
+    assert_eq!(val, -4.0);
+    store
+        .insert(b"indef", b"\xa1\x66Window\xbf\x64from\x21\x62to\x01\xff")
+        .unwrap();
+    let val: Mode = store.load_val_with::<_, _, 32>(&Cbor, b"indef").unwrap();
+    assert_eq!(
+        val,
+        Mode::Window {
+            from: -2,
+            to: Some(1)
+        }
+    );
+    store.insert(b"tail", &[0x01, 0x02]).unwrap();
+    let res = store.load_val_with::<_, u8, 32>(&Cbor, b"tail");
+    assert_eq!(
+        res.unwrap_err(),
+        kvs::Error::CodecError(kvs::CodecError::TrailingBytes)
+    );
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack_codec() {
+    use kvs::MessagePack;
+
+    round_trip(&MessagePack::default(), &[0x2a]);
+    round_trip(&MessagePack::default().named(), &[0x2a]);
+
+    let mut store = tiny::create_store();
+    let mut encoded_entry = [0; 32];
+    store
+        .encoded(&MessagePack::default().named())
+        .insert_val::<_, 32>(b"wifi", &TEST_ENTRY)
+        .unwrap();
+    let raw = store.load_slice(b"wifi", &mut encoded_entry).unwrap();
+    assert_eq!(raw[0], 0x82);
+    assert_eq!(&raw[1..6], b"\xa4ssid");
+
+    let res = store.insert_val_with::<_, _, 4>(&MessagePack::default(), b"wifi", &TEST_ENTRY);
+    let err = kvs::Error::CodecError(kvs::CodecError::BufferOverflow);
+    assert_eq!(res.unwrap_err(), err);
+    store.insert(b"bad", &[0xc1]).unwrap();
+    let res = store.load_val_with::<_, u32, 32>(&MessagePack::default(), b"bad");
+    assert_eq!(
+        res.unwrap_err(),
+        kvs::Error::CodecError(kvs::CodecError::Malformed)
+    );
+}
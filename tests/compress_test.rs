@@ -0,0 +1,196 @@
+use proptest::prelude::*;
+
+use byteorder::LittleEndian;
+
+use kvs::adapters::ram::*;
+use kvs::adapters::StoreAdapter;
+use kvs::{Compression, KVStore, StoreConfig, COMPRESSION_HEADER_LEN};
+
+const CONFIG: &[u8] =
+    br#"{"ssid":"home","retry":3,"log":"info"},{"ssid":"work","retry":3,"log":"warn"}"#;
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 2048;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_compressed_round_trip() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 128];
+    let mut packed = store.compressed(&mut scratch);
+    let bucket = packed.insert(b"cfg", CONFIG).unwrap();
+    assert!(bucket.val_len() < CONFIG.len());
+    assert_eq!(packed.codec(b"cfg").unwrap(), Compression::Lzss);
+    assert_eq!(packed.val_len(b"cfg").unwrap(), CONFIG.len());
+
+    let mut buf = [0; 128];
+    assert_eq!(packed.load_slice(b"cfg", &mut buf).unwrap(), CONFIG);
+    assert_eq!(
+        packed.load_slice(b"cfg", &mut buf[..16]).unwrap_err(),
+        kvs::Error::ValueOverflow
+    );
+}
+
+#[test]
+fn test_repeated_runs() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 64];
+    let mut packed = store.compressed(&mut scratch);
+
+    let mut log = [0; 600];
+    for (idx, byte) in log.iter_mut().enumerate() {
+        *byte = b"abc"[idx / 200];
+    }
+    let bucket = packed.insert(b"log", &log).unwrap();
+    assert!(bucket.val_len() < 32);
+
+    let mut buf = [0; 600];
+    assert_eq!(packed.load_slice(b"log", &mut buf).unwrap(), log);
+}
+
+#[test]
+fn test_incompressible_value() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 64];
+    let mut packed = store.compressed(&mut scratch);
+    let bucket = packed.insert(b"key", b"0123456789").unwrap();
+    assert_eq!(bucket.val_len(), COMPRESSION_HEADER_LEN + 10);
+    assert_eq!(packed.codec(b"key").unwrap(), Compression::None);
+
+    let mut buf = [0; 16];
+    assert_eq!(packed.load_str(b"key", &mut buf).unwrap(), "0123456789");
+}
+
+#[test]
+fn test_compression_opt_out() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 128];
+    let mut packed = store
+        .compressed(&mut scratch)
+        .compression(Compression::None);
+    let bucket = packed.insert(b"cfg", CONFIG).unwrap();
+    assert_eq!(bucket.val_len(), COMPRESSION_HEADER_LEN + CONFIG.len());
+
+    let mut buf = [0; 128];
+    assert_eq!(packed.load_slice(b"cfg", &mut buf).unwrap(), CONFIG);
+}
+
+#[test]
+fn test_corrupted_record() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 128];
+    store
+        .compressed(&mut scratch)
+        .insert(b"cfg", CONFIG)
+        .unwrap();
+    let addr = store.lookup(b"cfg").unwrap().val_address();
+    store.adapter().write(addr, &[7]).unwrap();
+    store.insert(b"raw", b"foo").unwrap();
+
+    let mut buf = [0; 128];
+    let mut packed = store.compressed(&mut scratch);
+    assert_eq!(
+        packed.load_slice(b"cfg", &mut buf).unwrap_err(),
+        kvs::Error::CorruptedValue
+    );
+    assert_eq!(packed.codec(b"raw").unwrap(), Compression::None);
+    assert_eq!(packed.load_slice(b"raw", &mut buf).unwrap(), b"foo");
+}
+
+#[test]
+fn test_core_mutators_reject_compressed() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 128];
+    let mut packed = store.compressed(&mut scratch);
+    packed.insert(b"cfg", CONFIG).unwrap();
+    packed.insert(b"key", b"0123456789").unwrap();
+    store.insert(b"raw", b"foo").unwrap();
+
+    let mut store = tiny::Store::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    )
+    .unwrap();
+    for key in [&b"cfg"[..], b"key"] {
+        let err = store.append(key, b"!").unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        let err = store.patch(key, 0, &[7]).unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        let err = store.truncate(key, 1).unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        let err = store.resize(key, 64, 0).unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        let err = store.load(key, &mut [0; 128]).unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        let err = store.compare_and_swap(key, b"", b"").unwrap_err();
+        assert_eq!(err, kvs::Error::CompressedValue);
+        assert!(store.lookup(key).unwrap().is_compressed());
+    }
+    store.append(b"raw", b"bar").unwrap();
+    assert!(!store.lookup(b"raw").unwrap().is_compressed());
+
+    let mut buf = [0; 128];
+    let mut packed = store.compressed(&mut scratch);
+    assert_eq!(packed.load_slice(b"cfg", &mut buf).unwrap(), CONFIG);
+    assert_eq!(packed.load_str(b"key", &mut buf).unwrap(), "0123456789");
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip_any(val in prop::collection::vec(0..4u8, 0..400)) {
+        let mut store = tiny::create_store();
+        let mut scratch = [0; 512];
+        let mut packed = store.compressed(&mut scratch);
+        packed.insert(b"key", &val).unwrap();
+
+        let mut buf = [0; 400];
+        prop_assert_eq!(packed.load_slice(b"key", &mut buf).unwrap(), &val[..]);
+    }
+}
+
+#[test]
+fn test_plain_records_beside_compressed() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 128];
+    store
+        .compressed(&mut scratch)
+        .insert(b"cfg", CONFIG)
+        .unwrap();
+
+    store.insert(b"zero", &[0; 5]).unwrap();
+    store.append(b"zero", b"!").unwrap();
+    store.insert(b"one", &[1, 0, 0, 0, 20, 7]).unwrap();
+    store.truncate(b"one", 5).unwrap();
+    store.set_u64::<LittleEndian>(b"boot", 256).unwrap();
+    assert_eq!(store.increment::<LittleEndian>(b"boot", 1).unwrap(), 257);
+    assert_eq!(store.increment::<LittleEndian>(b"boot", 1).unwrap(), 258);
+
+    let mut buf = [0; 8];
+    assert_eq!(store.load_slice(b"zero", &mut buf).unwrap(), b"\0\0\0\0\0!");
+    assert_eq!(
+        store.load_slice(b"one", &mut buf).unwrap(),
+        [1, 0, 0, 0, 20]
+    );
+
+    store.insert(b"cfg", b"plain").unwrap();
+    assert_eq!(store.load_slice(b"cfg", &mut buf).unwrap(), b"plain");
+    store.append(b"cfg", b"!").unwrap();
+}
@@ -0,0 +1,291 @@
+#![cfg(feature = "aead")]
+
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use kvs::adapters::ram::*;
+use kvs::adapters::StoreAdapter;
+use kvs::{KVStore, StoreConfig};
+
+const SECRET: &[u8] = b"correct-horse";
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 2048;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+
+    pub fn cipher() -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&[7; 32].into())
+    }
+}
+
+fn nonce(store: &mut tiny::Store, key: &[u8]) -> [u8; 12] {
+    let mut record = [0; 12];
+    store.load(key, &mut record).unwrap();
+    record
+}
+
+fn occurrences(store: &mut tiny::Store, pat: &[u8]) -> usize {
+    let mut memory = [0; tiny::STORE_SIZE];
+    store.adapter().read(0, &mut memory).unwrap();
+    memory.windows(pat.len()).filter(|win| *win == pat).count()
+}
+
+#[test]
+fn test_encrypted_round_trip() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch);
+    enc.insert(b"wifi/pass", SECRET).unwrap();
+    assert!(enc.exists(b"wifi/pass").unwrap());
+
+    let mut buf = [0; 32];
+    assert_eq!(enc.load_slice(b"wifi/pass", &mut buf).unwrap(), SECRET);
+
+    let bucket = store.lookup(b"wifi/pass").unwrap();
+    assert_eq!(bucket.val_len(), SECRET.len() + 12 + 16);
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+}
+
+#[test]
+fn test_encrypted_aes_gcm() {
+    let mut store = tiny::create_store();
+    let cipher = Aes256Gcm::new(&[3; 32].into());
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch);
+    enc.insert(b"api", SECRET).unwrap();
+
+    let mut buf = [0; 32];
+    assert_eq!(enc.load_str(b"api", &mut buf).unwrap(), "correct-horse");
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+}
+
+#[test]
+fn test_tamper_detection() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    store
+        .encrypted(&cipher, &mut scratch)
+        .insert(b"pass", SECRET)
+        .unwrap();
+
+    let bucket = store.lookup(b"pass").unwrap();
+    let addr = bucket.val_address() + 14;
+    let mut byte = [0];
+    store.adapter().read(addr, &mut byte).unwrap();
+    store.adapter().write(addr, &[byte[0] ^ 1]).unwrap();
+
+    let mut buf = [0; 32];
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .load_slice(b"pass", &mut buf)
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::AuthenticationFailed);
+
+    let other = ChaCha20Poly1305::new(&[8; 32].into());
+    store
+        .encrypted(&cipher, &mut scratch)
+        .insert(b"pass", SECRET)
+        .unwrap();
+    let err = store
+        .encrypted(&other, &mut scratch)
+        .load_slice(b"pass", &mut buf)
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::AuthenticationFailed);
+}
+
+#[test]
+fn test_swapped_records_rejected() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch);
+    enc.insert(b"foo", b"one").unwrap();
+    enc.insert(b"bar", b"two").unwrap();
+
+    let mut record = [0; 31];
+    store.load(b"foo", &mut record).unwrap();
+    store.insert(b"bar", &record).unwrap();
+
+    let mut buf = [0; 32];
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .load_slice(b"bar", &mut buf)
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::AuthenticationFailed);
+}
+
+#[test]
+fn test_encrypted_patch_and_append() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch);
+    enc.insert(b"foo", b"hello world").unwrap();
+    enc.patch(b"foo", 6, b"there").unwrap();
+    enc.append(b"foo", b"!").unwrap();
+
+    let mut buf = [0; 32];
+    assert_eq!(enc.load_slice(b"foo", &mut buf).unwrap(), b"hello there!");
+    assert_eq!(
+        enc.patch(b"foo", 13, b"x").unwrap_err(),
+        kvs::Error::InvalidPatchOffset
+    );
+    assert_eq!(
+        enc.append(b"foo", &[0; 32]).unwrap_err(),
+        kvs::Error::ValueOverflow
+    );
+}
+
+#[test]
+fn test_scratch_wiped_on_error() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    store
+        .encrypted(&cipher, &mut scratch)
+        .insert(b"wifi/pass", SECRET)
+        .unwrap();
+
+    let mut small = [0; 4];
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .load_slice(b"wifi/pass", &mut small)
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+    assert_eq!(scratch, [0; 64]);
+
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .patch(b"wifi/pass", 20, b"x")
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::InvalidPatchOffset);
+    assert_eq!(scratch, [0; 64]);
+
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .append(b"wifi/pass", &[0; 32])
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+    assert_eq!(scratch, [0; 64]);
+
+    let err = store
+        .encrypted(&cipher, &mut scratch)
+        .patch(b"wifi/pass", 0, &[0; 40])
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+    assert_eq!(scratch, [0; 64]);
+}
+
+#[test]
+fn test_empty_key() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch);
+    assert_eq!(enc.insert(b"", SECRET).unwrap_err(), kvs::Error::InvalidKey);
+    assert_eq!(enc.exists(b"").unwrap_err(), kvs::Error::InvalidKey);
+
+    let mut enc = enc.hide_keys();
+    assert_eq!(enc.insert(b"", SECRET).unwrap_err(), kvs::Error::InvalidKey);
+}
+
+#[test]
+fn test_nonce_per_write() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut nonces = Vec::new();
+    for idx in 0..80 {
+        let mut enc = store.encrypted(&cipher, &mut scratch);
+        enc.insert(b"foo", SECRET).unwrap();
+        if idx % 2 == 0 {
+            enc.patch(b"foo", 0, b"C").unwrap();
+        }
+
+        nonces.push(nonce(&mut store, b"foo"));
+    }
+
+    let mut unique = nonces.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), nonces.len());
+
+    let mut counter = [0; 8];
+    store
+        .adapter()
+        .read(tiny::STORE_SIZE - counter.len(), &mut counter)
+        .unwrap();
+    assert!(u64::from_be_bytes(counter) >= nonces.len() as u64);
+    assert_eq!(store.keys().count(), 1);
+}
+
+#[test]
+fn test_hidden_keys() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut enc = store.encrypted(&cipher, &mut scratch).hide_keys();
+    enc.insert(b"wifi/ssid", b"home").unwrap();
+    enc.insert(b"wifi/pass", SECRET).unwrap();
+
+    let mut buf = [0; 32];
+    assert_eq!(enc.load_slice(b"wifi/pass", &mut buf).unwrap(), SECRET);
+    enc.remove(b"wifi/ssid").unwrap();
+    assert!(!enc.exists(b"wifi/ssid").unwrap());
+
+    assert!(!store.exists(b"wifi/pass").unwrap());
+    assert_eq!(occurrences(&mut store, b"wifi"), 0);
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+}
+
+#[test]
+fn test_nonce_survives_clear() {
+    let mut store = tiny::create_store();
+    let cipher = tiny::cipher();
+    let mut scratch = [0; 64];
+    let mut nonces = Vec::new();
+    for round in 0..5 {
+        let mut enc = store.encrypted(&cipher, &mut scratch);
+        enc.insert(b"n/foo", SECRET).unwrap();
+        enc.insert(b"n/bar", SECRET).unwrap();
+        nonces.push(nonce(&mut store, b"n/foo"));
+        nonces.push(nonce(&mut store, b"n/bar"));
+
+        match round {
+            0 => assert_eq!(store.remove_prefix(b"").unwrap(), 2),
+            1 => assert_eq!(store.erase_prefix(b"", 0xff).unwrap(), 2),
+            2 => assert_eq!(store.namespace(b"n/").clear().unwrap(), 2),
+            3 => store.reset().unwrap(),
+            _ => {
+                store = tiny::Store::open(
+                    store.close(),
+                    StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+                    false,
+                )
+                .unwrap()
+            }
+        }
+    }
+
+    let mut unique = nonces.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), nonces.len());
+}
@@ -0,0 +1,134 @@
+use kvs::adapters::ram::*;
+use kvs::{DynKVStore, KVStore, Slot, StoreConfig};
+
+mod tiny {
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const MAX_HOPS: usize = 32;
+}
+
+#[test]
+fn test_create_dyn_store() {
+    let mut slots = [Slot::default(); 8];
+    let mut store = DynKVStore::create(
+        MemoryAdapter::<{ tiny::STORE_SIZE }>::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        48,
+        &mut slots,
+    )
+    .unwrap();
+    assert_eq!(store.buckets(), 48);
+
+    store.insert(b"foo", b"bar").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.buckets, 48);
+    assert_eq!(stats.slots, 8);
+    assert_eq!(stats.used_buckets, 1);
+}
+
+#[test]
+fn test_open_dyn_store() {
+    let mut store = KVStore::<MemoryAdapter<{ tiny::STORE_SIZE }>, 32, 8>::open(
+        MemoryAdapter::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        true,
+    )
+    .unwrap();
+    store.insert(b"foo", b"bar").unwrap();
+    let adapter = store.close();
+
+    let mut slots = [Slot::default(); 4];
+    let mut store = DynKVStore::open(
+        adapter,
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        &mut slots,
+    )
+    .unwrap();
+    assert_eq!(store.buckets(), 32);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
+    store.insert(b"baz", b"qux").unwrap();
+    let adapter = store.close();
+
+    let mut store = KVStore::<MemoryAdapter<{ tiny::STORE_SIZE }>, 32, 8>::open(
+        adapter,
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    )
+    .unwrap();
+    assert_eq!(store.load_slice(b"baz", &mut buf).unwrap(), b"qux");
+}
+
+#[test]
+fn test_open_dyn_store_read_only() {
+    let mut slots = [Slot::default(); 8];
+    let mut store = DynKVStore::create(
+        MemoryAdapter::<{ tiny::STORE_SIZE }>::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        16,
+        &mut slots,
+    )
+    .unwrap();
+    store.insert(b"foo", b"bar").unwrap();
+    let adapter = store.close();
+
+    let mut store = DynKVStore::open(
+        adapter,
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        &mut [],
+    )
+    .unwrap();
+    assert!(store.is_read_only());
+    assert!(store.exists(b"foo").unwrap());
+    assert_eq!(
+        store.insert(b"baz", b"qux").unwrap_err(),
+        kvs::Error::ReadOnlyStore
+    );
+}
+
+#[test]
+fn test_invalid_bucket_count() {
+    let mut slots = [Slot::default(); 8];
+    let res = DynKVStore::create(
+        MemoryAdapter::<{ tiny::STORE_SIZE }>::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        0,
+        &mut slots,
+    );
+    assert!(matches!(res, Err(kvs::Error::InvalidCapacity)));
+}
+
+#[test]
+fn test_dyn_rehash() {
+    let mut slots = [Slot::default(); 8];
+    let mut store = DynKVStore::create(
+        MemoryAdapter::<2048>::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        16,
+        &mut slots,
+    )
+    .unwrap();
+    for idx in 0..10u8 {
+        store.insert(&[b'k', idx], &[idx; 4]).unwrap();
+    }
+    let adapter = store.close();
+
+    let mut slots = [Slot::default(); 8];
+    let mut store = DynKVStore::rehash(
+        adapter,
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        40,
+        &mut slots,
+    )
+    .unwrap();
+    assert_eq!(store.buckets(), 40);
+
+    let mut buf = [0; 4];
+    for idx in 0..10u8 {
+        assert_eq!(store.load_slice(&[b'k', idx], &mut buf).unwrap(), [idx; 4]);
+    }
+}
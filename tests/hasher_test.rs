@@ -0,0 +1,90 @@
+use kvs::adapters::ram::*;
+use kvs::{BucketLayout, HashAlgorithm, KVStore, StoreConfig};
+
+const KEYS: [&str; 4] = [
+    "/bin/charlotte/big/great/year.jpg",
+    "/bin/group/place/public.txt",
+    "/bin/oliver/fact/year/place.txt",
+    "/etc/able/woman/mia.mp4",
+];
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store(cfg: StoreConfig) -> Store {
+        Store::open(MemoryAdapter::default(), cfg, true).unwrap()
+    }
+}
+
+#[test]
+fn test_store_with_hasher() {
+    for hasher in [
+        HashAlgorithm::Murmur3,
+        HashAlgorithm::Fnv1a,
+        HashAlgorithm::SipHash,
+    ] {
+        let cfg = StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS)
+            .nonce(7)
+            .hash_algorithm(hasher)
+            .bucket_layout(BucketLayout::Wide);
+        let mut store = tiny::create_store(cfg);
+        for key in KEYS {
+            store.insert(key.as_bytes(), b"foo").unwrap();
+        }
+
+        let mut store = tiny::Store::open(
+            store.close(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(7),
+            false,
+        )
+        .unwrap();
+        assert_eq!(store.hash_algorithm(), hasher);
+
+        let mut buf = [0; 8];
+        for key in KEYS {
+            assert_eq!(store.load_slice(key.as_bytes(), &mut buf).unwrap(), b"foo");
+        }
+    }
+}
+
+#[test]
+fn test_rehash_with_other_hasher() {
+    let mut store = tiny::create_store(StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS));
+    for key in KEYS {
+        store.insert(key.as_bytes(), b"foo").unwrap();
+    }
+
+    let mut store = tiny::Store::rehash(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).hash_algorithm(HashAlgorithm::SipHash),
+    )
+    .unwrap();
+    assert_eq!(store.hash_algorithm(), HashAlgorithm::SipHash);
+
+    let mut buf = [0; 8];
+    for key in KEYS {
+        assert_eq!(store.load_slice(key.as_bytes(), &mut buf).unwrap(), b"foo");
+    }
+}
+
+#[test]
+fn test_unknown_hasher() {
+    let store = tiny::create_store(StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS));
+    let mut memory = store.close().release();
+    memory[11] |= 0x06;
+
+    let res = tiny::Store::open(
+        MemoryAdapter::new(memory),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    );
+    assert!(matches!(res, Err(kvs::Error::UnsupportedVersion)));
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use kvs::adapters::ram::*;
+use kvs::adapters::StoreAdapter;
+use kvs::{Address, BucketLayout, KVStore, StoreConfig, MAX_KEY_LEN, MAX_VALUE_LEN};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store(layout: BucketLayout) -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS).bucket_layout(layout),
+            true,
+        )
+        .unwrap()
+    }
+
+    pub fn create_store_auto() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+mod large {
+    use crate::*;
+
+    pub const STORE_SIZE: usize = 72 * 1024;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, 8, 4>;
+
+    pub fn create_store(layout: BucketLayout) -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).bucket_layout(layout),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+mod huge {
+    use crate::*;
+
+    pub const STORE_SIZE: usize = 32 * 1024 * 1024;
+
+    #[derive(Default)]
+    pub struct SparseAdapter {
+        memory: HashMap<Address, u8>,
+    }
+
+    impl StoreAdapter for SparseAdapter {
+        type Error = ();
+
+        fn read(&mut self, addr: Address, buf: &mut [u8]) -> Result<(), Self::Error> {
+            if addr + buf.len() > STORE_SIZE {
+                return Err(());
+            }
+            for (offset, byte) in buf.iter_mut().enumerate() {
+                *byte = self.memory.get(&(addr + offset)).copied().unwrap_or(0);
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, addr: Address, data: &[u8]) -> Result<(), Self::Error> {
+            if addr + data.len() > STORE_SIZE {
+                return Err(());
+            }
+            for (offset, byte) in data.iter().enumerate() {
+                self.memory.insert(addr + offset, *byte);
+            }
+            Ok(())
+        }
+
+        fn max_address(&self) -> Address {
+            STORE_SIZE
+        }
+    }
+
+    pub type Store = KVStore<SparseAdapter, 512, 4>;
+
+    pub fn create_store(cfg: StoreConfig) -> Store {
+        Store::open(SparseAdapter::default(), cfg, true).unwrap()
+    }
+}
+
+#[test]
+fn test_compact_limits() {
+    let mut store = tiny::create_store(BucketLayout::Compact);
+
+    let key = [b'x'; MAX_KEY_LEN];
+    store.insert(&key[..MAX_KEY_LEN - 1], b"foo").unwrap();
+    assert!(store.exists(&key[..MAX_KEY_LEN - 1]).unwrap());
+
+    let err = store.insert(&key, b"foo").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyOverflow);
+    let err = store.exists(&key).unwrap_err();
+    assert_eq!(err, kvs::Error::KeyOverflow);
+
+    let mut store = large::create_store(BucketLayout::Compact);
+    let err = store.alloc(b"foo", MAX_VALUE_LEN, None).unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+    store.alloc(b"foo", MAX_VALUE_LEN - 1, None).unwrap();
+    let err = store.append(b"foo", b"x").unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+}
+
+#[test]
+fn test_wide_full_key() {
+    let mut store = tiny::create_store(BucketLayout::Wide);
+    assert_eq!(store.bucket_layout(), BucketLayout::Wide);
+
+    let key = [b'x'; MAX_KEY_LEN];
+    store.insert(&key, b"foo").unwrap();
+
+    let mut buf = [0; 8];
+    assert_eq!(store.load_slice(&key, &mut buf).unwrap(), b"foo");
+
+    let key_ref = store.keys().next().unwrap();
+    assert_eq!(key_ref.key(), &key[..]);
+
+    let err = store.insert(&[b'x'; MAX_KEY_LEN + 1], b"foo").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyOverflow);
+}
+
+#[test]
+fn test_wide_full_value() {
+    let mut store = large::create_store(BucketLayout::Wide);
+
+    let bucket = store.alloc(b"foo", MAX_VALUE_LEN, Some(0xaa)).unwrap();
+    assert_eq!(bucket.val_len(), MAX_VALUE_LEN);
+
+    let mut buf = [0; 4];
+    let bucket = store.load_at(b"foo", &mut buf, MAX_VALUE_LEN - 4).unwrap();
+    assert_eq!(bucket.val_len(), MAX_VALUE_LEN);
+    assert_eq!(buf, [0xaa; 4]);
+
+    let err = store.append(b"foo", b"x").unwrap_err();
+    assert_eq!(err, kvs::Error::ValueOverflow);
+}
+
+#[test]
+fn test_reopen_wide_store() {
+    let mut store = tiny::create_store(BucketLayout::Wide);
+    store.insert(&[b'x'; MAX_KEY_LEN], b"foo").unwrap();
+
+    let mut store = tiny::Store::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    )
+    .unwrap();
+    assert_eq!(store.bucket_layout(), BucketLayout::Wide);
+
+    let mut buf = [0; 8];
+    let val = store.load_slice(&[b'x'; MAX_KEY_LEN], &mut buf).unwrap();
+    assert_eq!(val, b"foo");
+}
+
+#[test]
+fn test_layout_for_store_size() {
+    let store = tiny::create_store_auto();
+    assert_eq!(store.bucket_layout(), BucketLayout::Compact);
+
+    let store = huge::create_store(StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS));
+    assert_eq!(store.bucket_layout(), BucketLayout::Wide);
+}
+
+#[test]
+fn test_large_store_addressing() {
+    let mut store = huge::create_store(StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS));
+    assert_eq!(
+        store.stats().unwrap().data_size,
+        huge::STORE_SIZE - 16 - 16 * 512
+    );
+
+    for idx in 0..260u16 {
+        store
+            .alloc(&idx.to_be_bytes(), MAX_VALUE_LEN, None)
+            .unwrap();
+    }
+    let bucket = store.insert(b"foo", b"bar").unwrap();
+    assert!(bucket.val_address() > 1 << 24);
+
+    let mut store = huge::Store::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        false,
+    )
+    .unwrap();
+    let mut buf = [0; 8];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
+}
+
+#[test]
+fn test_compact_layout_on_large_store() {
+    let cfg = StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).bucket_layout(BucketLayout::Compact);
+    let mut store = huge::create_store(cfg);
+    assert_eq!(store.bucket_layout(), BucketLayout::Compact);
+    assert_eq!(store.stats().unwrap().data_size, (1 << 24) - 16 - 8 * 512);
+
+    for idx in 0..255u16 {
+        store
+            .alloc(&idx.to_be_bytes(), MAX_VALUE_LEN - 1, None)
+            .unwrap();
+    }
+    let err = store.alloc(b"foo", MAX_VALUE_LEN - 1, None).unwrap_err();
+    assert_eq!(err, kvs::Error::StoreOverflow);
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+
+use kvs::adapters::ram::*;
+use kvs::{KVStore, ProbeStrategy, StoreConfig};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 4096;
+    pub const BUCKETS: usize = 64;
+    pub const SLOTS: usize = 64;
+    pub const MAX_HOPS: usize = 16;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store(strategy: ProbeStrategy) -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS).probe_strategy(strategy),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8, Vec<u8>),
+    Remove(u8),
+    Load(u8),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..24u8, prop::collection::vec(any::<u8>(), 0..8)).prop_map(|(k, v)| Op::Insert(k, v)),
+        (0..24u8).prop_map(Op::Remove),
+        (0..24u8).prop_map(Op::Load),
+    ]
+}
+
+fn probe_strategy() -> impl Strategy<Value = ProbeStrategy> {
+    prop_oneof![
+        Just(ProbeStrategy::Rehash),
+        Just(ProbeStrategy::Linear),
+        Just(ProbeStrategy::RobinHood),
+        Just(ProbeStrategy::Cuckoo),
+    ]
+}
+
+fn key(idx: u8) -> [u8; 3] {
+    [b'k', b'/', idx]
+}
+
+proptest! {
+    #[test]
+    fn test_matches_hashmap(
+        strategy in probe_strategy(),
+        ops in prop::collection::vec(op(), 1..64),
+    ) {
+        let mut store = tiny::create_store(strategy);
+        let mut model: HashMap<u8, Vec<u8>> = HashMap::new();
+        let mut buf = [0; 8];
+
+        for op in ops {
+            match op {
+                Op::Insert(k, v) => match store.insert(&key(k), &v) {
+                    Ok(_) => {
+                        model.insert(k, v);
+                    }
+                    Err(kvs::Error::IndexOverflow) => prop_assert!(!model.contains_key(&k)),
+                    Err(err) => prop_assert!(false, "insert failed: {:?}", err),
+                },
+                Op::Remove(k) => {
+                    store.remove(&key(k)).unwrap();
+                    model.remove(&k);
+                }
+                Op::Load(k) => match model.get(&k) {
+                    Some(val) => {
+                        prop_assert_eq!(store.load_slice(&key(k), &mut buf).unwrap(), &val[..]);
+                    }
+                    None => prop_assert!(!store.exists(&key(k)).unwrap()),
+                },
+            }
+        }
+
+        for k in 0..24u8 {
+            prop_assert_eq!(store.exists(&key(k)).unwrap(), model.contains_key(&k));
+        }
+        prop_assert_eq!(store.keys().count(), model.len());
+        prop_assert_eq!(store.stats().unwrap().used_buckets, model.len());
+    }
+}
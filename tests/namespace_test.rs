@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+
+use kvs::adapters::ram::*;
+use kvs::{KVStore, ProbeStrategy, StoreConfig};
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store() -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn test_prefixed_keys() {
+    let mut store = tiny::create_store();
+
+    store.namespace(b"net/").insert(b"ssid", b"foo").unwrap();
+    store.namespace(b"sensor/").insert(b"ssid", b"bar").unwrap();
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"net/ssid", &mut scratch).unwrap();
+    assert_eq!(val, b"foo");
+
+    let mut ns = store.namespace(b"sensor/");
+    let val = ns.load_slice(b"ssid", &mut scratch).unwrap();
+    assert_eq!(val, b"bar");
+    assert!(ns.exists(b"ssid").unwrap());
+    assert!(!ns.exists(b"net/ssid").unwrap());
+}
+
+#[test]
+fn test_list_keys() {
+    let mut check = HashSet::new();
+    check.insert("foo");
+    check.insert("bar");
+
+    let mut store = tiny::create_store();
+    store.insert(b"net/foo", b"bar").unwrap();
+    store.insert(b"net/bar", b"baz").unwrap();
+    store.insert(b"log/foo", b"baz").unwrap();
+
+    for key_ref in store.namespace(b"net/").keys() {
+        let key = core::str::from_utf8(key_ref.key()).unwrap();
+        assert!(check.remove(key));
+    }
+
+    assert_eq!(check.len(), 0);
+}
+
+#[test]
+fn test_clear() {
+    let mut store = tiny::create_store();
+    store.insert(b"log/1", b"foo").unwrap();
+    store.insert(b"log/2", b"bar").unwrap();
+    store.insert(b"net/ssid", b"baz").unwrap();
+
+    assert_eq!(store.namespace(b"log/").clear().unwrap(), 2);
+    assert!(!store.exists(b"log/1").unwrap());
+    assert!(!store.exists(b"log/2").unwrap());
+    assert!(store.exists(b"net/ssid").unwrap());
+    assert_eq!(store.namespace(b"log/").keys().count(), 0);
+}
+
+#[test]
+fn test_clear_all_strategies() {
+    for strategy in [
+        ProbeStrategy::Rehash,
+        ProbeStrategy::Linear,
+        ProbeStrategy::RobinHood,
+        ProbeStrategy::Cuckoo,
+    ] {
+        let mut store = KVStore::<MemoryAdapter<2048>, 64, 8>::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).probe_strategy(strategy),
+            true,
+        )
+        .unwrap();
+        for idx in 0..30 {
+            store.insert(format!("n/{idx}").as_bytes(), b"foo").unwrap();
+        }
+        store.insert(b"other", b"bar").unwrap();
+
+        assert_eq!(store.namespace(b"n/").clear().unwrap(), 30);
+        assert_eq!(store.namespace(b"n/").keys().count(), 0);
+        for idx in 0..30 {
+            assert!(!store.exists(format!("n/{idx}").as_bytes()).unwrap());
+        }
+        assert!(store.exists(b"other").unwrap());
+    }
+}
+
+#[test]
+fn test_key_overflow() {
+    let mut store = tiny::create_store();
+    let key = [b'x'; kvs::MAX_KEY_LEN];
+
+    let err = store.namespace(b"net/").insert(&key, b"foo").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyOverflow);
+}
+
+#[test]
+fn test_empty_key() {
+    let mut store = tiny::create_store();
+    let mut ns = store.namespace(b"net/").quota(64);
+
+    for _ in 0..2 {
+        let err = ns.insert(b"", b"abc").unwrap_err();
+        assert_eq!(err, kvs::Error::InvalidKey);
+    }
+    assert_eq!(ns.usage().unwrap(), 0);
+    assert!(!store.exists(b"net/").unwrap());
+}
+
+#[test]
+fn test_quota() {
+    let mut store = tiny::create_store();
+    store.insert(b"log/1", b"foobar").unwrap();
+
+    let mut ns = store.namespace(b"net/").quota(16);
+    ns.insert(b"ssid", b"foo").unwrap();
+    assert_eq!(ns.usage().unwrap(), 11);
+
+    let err = ns.insert(b"pass", b"barbaz").unwrap_err();
+    assert_eq!(err, kvs::Error::QuotaExceeded);
+    assert!(!ns.exists(b"pass").unwrap());
+
+    ns.insert(b"ssid", b"foobar").unwrap();
+    assert_eq!(ns.usage().unwrap(), 14);
+
+    let err = ns.append(b"ssid", b"baz").unwrap_err();
+    assert_eq!(err, kvs::Error::QuotaExceeded);
+    ns.patch(b"ssid", 6, b"xy").unwrap();
+    assert_eq!(ns.usage().unwrap(), 16);
+
+    ns.remove(b"ssid").unwrap();
+    assert_eq!(ns.usage().unwrap(), 0);
+    ns.insert(b"pass", b"barbaz").unwrap();
+}
+
+#[test]
+fn test_quota_counts_existing_keys() {
+    let mut store = tiny::create_store();
+    store.insert(b"net/ssid", b"foobar").unwrap();
+
+    let mut ns = store.namespace(b"net/").quota(16);
+    assert_eq!(ns.usage().unwrap(), 14);
+
+    let err = ns.insert(b"pass", b"foo").unwrap_err();
+    assert_eq!(err, kvs::Error::QuotaExceeded);
+}
+
+#[test]
+fn test_quota_resize() {
+    let mut store = tiny::create_store();
+    let mut ns = store.namespace(b"net/").quota(16);
+    ns.insert(b"ssid", b"foobar").unwrap();
+
+    let err = ns.resize(b"ssid", 9, 0).unwrap_err();
+    assert_eq!(err, kvs::Error::QuotaExceeded);
+    ns.resize(b"ssid", 8, 0).unwrap();
+    assert_eq!(ns.usage().unwrap(), 16);
+
+    ns.truncate(b"ssid", 3).unwrap();
+    assert_eq!(ns.usage().unwrap(), 11);
+    let mut buf = [0; 16];
+    assert_eq!(ns.load_slice(b"ssid", &mut buf).unwrap(), b"foo");
+}
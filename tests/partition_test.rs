@@ -0,0 +1,93 @@
+use core::cell::RefCell;
+
+use kvs::adapters::partition::*;
+use kvs::adapters::ram::*;
+use kvs::adapters::StoreAdapter;
+use kvs::{KVStore, StoreConfig};
+
+const STORE_SIZE: usize = 2048;
+
+const FACTORY_MAGIC: u32 = 0x66616374;
+const USER_MAGIC: u32 = 0x75736572;
+const LOG_MAGIC: u32 = 0x6c6f6773;
+
+type FactoryStore<'a> = KVStore<PartitionAdapter<'a, MemoryAdapter<STORE_SIZE>>, 8, 4>;
+type UserStore<'a> = KVStore<PartitionAdapter<'a, MemoryAdapter<STORE_SIZE>>, 16, 8>;
+type LogStore<'a> = KVStore<PartitionAdapter<'a, MemoryAdapter<STORE_SIZE>>, 4, 2>;
+
+#[test]
+fn test_partitions() {
+    let memory = RefCell::new(MemoryAdapter::<STORE_SIZE>::default());
+
+    let mut factory = FactoryStore::open(
+        PartitionAdapter::new(&memory, 0, 512),
+        StoreConfig::new(FACTORY_MAGIC, 8),
+        true,
+    )
+    .unwrap();
+    let mut user = UserStore::open(
+        PartitionAdapter::new(&memory, 512, 1024),
+        StoreConfig::new(USER_MAGIC, 16),
+        true,
+    )
+    .unwrap();
+    let mut log = LogStore::open(
+        PartitionAdapter::new(&memory, 1536, 512),
+        StoreConfig::new(LOG_MAGIC, 4),
+        true,
+    )
+    .unwrap();
+
+    factory.insert(b"serial", b"0001").unwrap();
+    user.insert(b"serial", b"0002").unwrap();
+    log.insert(b"serial", b"0003").unwrap();
+
+    let mut scratch = [0; 16];
+    assert_eq!(
+        factory.load_slice(b"serial", &mut scratch).unwrap(),
+        b"0001"
+    );
+    assert_eq!(user.load_slice(b"serial", &mut scratch).unwrap(), b"0002");
+    assert_eq!(log.load_slice(b"serial", &mut scratch).unwrap(), b"0003");
+
+    log.reset().unwrap();
+    assert!(!log.exists(b"serial").unwrap());
+    assert!(factory.exists(b"serial").unwrap());
+    assert!(user.exists(b"serial").unwrap());
+
+    factory.close();
+    user.close();
+    log.close();
+
+    let mut user = UserStore::open(
+        PartitionAdapter::new(&memory, 512, 1024),
+        StoreConfig::new(USER_MAGIC, 16),
+        false,
+    )
+    .unwrap();
+    assert_eq!(user.load_slice(b"serial", &mut scratch).unwrap(), b"0002");
+
+    let err = UserStore::open(
+        PartitionAdapter::new(&memory, 0, 1024),
+        StoreConfig::new(USER_MAGIC, 16),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err, kvs::Error::StoreNotFound);
+}
+
+#[test]
+fn test_out_of_bounds() {
+    let memory = RefCell::new(MemoryAdapter::<STORE_SIZE>::default());
+    let mut partition = PartitionAdapter::new(&memory, 1024, 16);
+
+    let mut scratch = [0; 8];
+    partition.write(8, b"foobar42").unwrap();
+    partition.read(8, &mut scratch).unwrap();
+    assert_eq!(&scratch, b"foobar42");
+    assert_eq!(&memory.borrow().memory[1032..1040], b"foobar42");
+
+    assert_eq!(partition.read(9, &mut scratch), Err(Error::OutOfBounds));
+    assert_eq!(partition.write(16, &[0]), Err(Error::OutOfBounds));
+}
@@ -0,0 +1,229 @@
+use kvs::adapters::ram::*;
+use kvs::{KVStore, ProbeStrategy, StoreConfig};
+
+const STRATEGIES: [ProbeStrategy; 4] = [
+    ProbeStrategy::Rehash,
+    ProbeStrategy::Linear,
+    ProbeStrategy::RobinHood,
+    ProbeStrategy::Cuckoo,
+];
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 2048;
+    pub const BUCKETS: usize = 64;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store(strategy: ProbeStrategy) -> Store {
+        Store::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(MAGIC, MAX_HOPS).probe_strategy(strategy),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+fn key(idx: u8) -> [u8; 4] {
+    [b'k', b'e', b'y', idx]
+}
+
+#[test]
+fn test_insert_and_load() {
+    for strategy in STRATEGIES {
+        let mut store = tiny::create_store(strategy);
+        for idx in 0..32 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+        }
+
+        let mut buf = [0; 4];
+        for idx in 0..32 {
+            let val = store.load_slice(&key(idx), &mut buf).unwrap();
+            assert_eq!(val, [idx; 4], "{:?}", strategy);
+        }
+        assert!(!store.exists(b"missing").unwrap());
+        assert_eq!(store.stats().unwrap().used_buckets, 32);
+    }
+}
+
+#[test]
+fn test_update_after_remove() {
+    for strategy in STRATEGIES {
+        let mut store = tiny::create_store(strategy);
+        for idx in 0..32 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+        }
+        for idx in (0..32).step_by(2) {
+            store.remove(&key(idx)).unwrap();
+        }
+        for idx in (1..32).step_by(2) {
+            store.insert(&key(idx), &[idx; 2]).unwrap();
+        }
+
+        let mut buf = [0; 4];
+        for idx in 0..32 {
+            if idx % 2 == 0 {
+                assert!(!store.exists(&key(idx)).unwrap(), "{:?}", strategy);
+            } else {
+                let val = store.load_slice(&key(idx), &mut buf).unwrap();
+                assert_eq!(val, [idx; 2], "{:?}", strategy);
+            }
+        }
+        assert_eq!(store.stats().unwrap().used_buckets, 16, "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_reopen_with_strategy() {
+    for strategy in STRATEGIES {
+        let mut store = tiny::create_store(strategy);
+        for idx in 0..16 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+        }
+
+        let mut store = tiny::Store::open(
+            store.close(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+            false,
+        )
+        .unwrap();
+        assert_eq!(store.probe_strategy(), strategy);
+
+        let mut buf = [0; 4];
+        for idx in 0..16 {
+            let val = store.load_slice(&key(idx), &mut buf).unwrap();
+            assert_eq!(val, [idx; 4]);
+        }
+    }
+}
+
+#[test]
+fn test_cuckoo_probe_distance() {
+    let mut store = tiny::create_store(ProbeStrategy::Cuckoo);
+    for idx in 0..40 {
+        store.insert(&key(idx), &[idx]).unwrap();
+    }
+    assert!(store.stats().unwrap().max_probe_distance <= 2);
+}
+
+#[test]
+fn test_rehash_to_strategy() {
+    let mut store = tiny::create_store(ProbeStrategy::Rehash);
+    for idx in 0..24 {
+        store.insert(&key(idx), &[idx; 4]).unwrap();
+    }
+
+    for strategy in [ProbeStrategy::RobinHood, ProbeStrategy::Cuckoo] {
+        store = tiny::Store::rehash(
+            store.close(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).probe_strategy(strategy),
+        )
+        .unwrap();
+        assert_eq!(store.probe_strategy(), strategy);
+
+        let mut buf = [0; 4];
+        for idx in 0..24 {
+            let val = store.load_slice(&key(idx), &mut buf).unwrap();
+            assert_eq!(val, [idx; 4]);
+        }
+    }
+}
+
+#[test]
+fn test_rename_keys() {
+    for strategy in STRATEGIES {
+        let mut store = tiny::create_store(strategy);
+        for idx in 0..24 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+        }
+        for idx in 0..24 {
+            store.rename(&key(idx), &[b'n', idx]).unwrap();
+        }
+
+        let mut buf = [0; 4];
+        for idx in 0..24 {
+            assert!(!store.exists(&key(idx)).unwrap(), "{:?}", strategy);
+            let val = store.load_slice(&[b'n', idx], &mut buf).unwrap();
+            assert_eq!(val, [idx; 4], "{:?}", strategy);
+        }
+        assert_eq!(store.stats().unwrap().used_buckets, 24, "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_remove_prefix() {
+    for strategy in STRATEGIES {
+        let mut store = tiny::create_store(strategy);
+        for idx in 0..12 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+            store.insert(&[b'n', idx], &[idx; 4]).unwrap();
+        }
+
+        assert_eq!(store.remove_prefix(b"key").unwrap(), 12, "{:?}", strategy);
+        let mut buf = [0; 4];
+        for idx in 0..12 {
+            assert!(!store.exists(&key(idx)).unwrap(), "{:?}", strategy);
+            let val = store.load_slice(&[b'n', idx], &mut buf).unwrap();
+            assert_eq!(val, [idx; 4], "{:?}", strategy);
+        }
+    }
+}
+
+#[test]
+fn test_insert_probes_once() {
+    use kvs::adapters::StoreAdapter;
+    use kvs::Address;
+
+    #[derive(Default)]
+    struct CountingAdapter {
+        memory: MemoryAdapter<{ tiny::STORE_SIZE }>,
+        reads: usize,
+    }
+
+    impl StoreAdapter for CountingAdapter {
+        type Error = ();
+
+        fn read(&mut self, addr: Address, buf: &mut [u8]) -> Result<(), Self::Error> {
+            self.reads += 1;
+            self.memory.read(addr, buf)
+        }
+
+        fn write(&mut self, addr: Address, data: &[u8]) -> Result<(), Self::Error> {
+            self.memory.write(addr, data)
+        }
+
+        fn max_address(&self) -> Address {
+            self.memory.max_address()
+        }
+    }
+
+    for strategy in [ProbeStrategy::Rehash, ProbeStrategy::Linear] {
+        let mut store = KVStore::<CountingAdapter, { tiny::BUCKETS }, { tiny::SLOTS }>::open(
+            CountingAdapter::default(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).probe_strategy(strategy),
+            true,
+        )
+        .unwrap();
+        for idx in 0..48 {
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+        }
+        for idx in (0..48).step_by(3) {
+            store.remove(&key(idx)).unwrap();
+        }
+
+        for idx in 48..64 {
+            store.adapter().reads = 0;
+            assert!(!store.exists(&key(idx)).unwrap());
+            let lookup = store.adapter().reads;
+
+            store.adapter().reads = 0;
+            store.insert(&key(idx), &[idx; 4]).unwrap();
+            assert_eq!(store.adapter().reads, lookup, "{:?}", strategy);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use kvs::adapters::ram::*;
+use kvs::adapters::StoreAdapter;
+use kvs::{KVStore, StoreConfig};
+
+const SECRET: &[u8] = b"correct-horse";
+
+mod tiny {
+    use crate::*;
+
+    pub const MAGIC: u32 = 0x796e6974;
+    pub const STORE_SIZE: usize = 1024;
+    pub const BUCKETS: usize = 32;
+    pub const SLOTS: usize = 8;
+    pub const MAX_HOPS: usize = 32;
+
+    pub type Store = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, SLOTS>;
+
+    pub fn create_store(cfg: StoreConfig) -> Store {
+        Store::open(MemoryAdapter::default(), cfg, true).unwrap()
+    }
+
+    pub fn create_secure_store() -> Store {
+        create_store(StoreConfig::new(MAGIC, MAX_HOPS).secure_erase(0xff))
+    }
+}
+
+fn occurrences(store: &mut tiny::Store, pat: &[u8]) -> usize {
+    let mut memory = [0; tiny::STORE_SIZE];
+    store.adapter().read(0, &mut memory).unwrap();
+    memory.windows(pat.len()).filter(|win| *win == pat).count()
+}
+
+#[test]
+fn test_scrub_on_overwrite() {
+    let mut store = tiny::create_secure_store();
+    store.insert(b"pass", SECRET).unwrap();
+    store.insert(b"user", b"admin").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 1);
+
+    store.insert(b"pass", b"foo").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"pass", &mut buf).unwrap(), b"foo");
+    assert_eq!(store.load_slice(b"user", &mut buf).unwrap(), b"admin");
+}
+
+#[test]
+fn test_scrub_on_remove() {
+    let mut store = tiny::create_secure_store();
+    store.insert(b"pass", SECRET).unwrap();
+    store.remove(b"pass").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+
+    store.insert(b"log/pass", SECRET).unwrap();
+    store.remove_prefix(b"log/").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+
+    store.insert(b"pass", SECRET).unwrap();
+    store.truncate(b"pass", 7).unwrap();
+    assert_eq!(occurrences(&mut store, &SECRET[7..]), 0);
+}
+
+#[test]
+fn test_scrub_on_relocation() {
+    let mut store = tiny::create_secure_store();
+    store.insert(b"pass", SECRET).unwrap();
+    store.insert(b"user", b"admin").unwrap();
+
+    store.append(b"pass", b"!").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 1);
+
+    store.rename(b"pass", b"password").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 1);
+
+    let mut buf = [0; 16];
+    assert_eq!(
+        store.load_slice(b"password", &mut buf).unwrap(),
+        b"correct-horse!"
+    );
+}
+
+#[test]
+fn test_scrub_free_space() {
+    let mut store = tiny::create_store(StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS));
+    store.insert(b"pass", SECRET).unwrap();
+    store.insert(b"user", b"admin").unwrap();
+    store.remove(b"pass").unwrap();
+    assert_eq!(occurrences(&mut store, SECRET), 1);
+
+    let bytes_free = store.stats().unwrap().bytes_free;
+    assert_eq!(store.scrub_free_space().unwrap(), bytes_free);
+    assert_eq!(occurrences(&mut store, SECRET), 0);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"user", &mut buf).unwrap(), b"admin");
+}
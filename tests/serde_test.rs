@@ -1,7 +1,8 @@
 #![cfg(feature = "serde")]
 
 use kvs::adapters::ram::*;
-use kvs::{KVStore, StoreConfig};
+use kvs::adapters::StoreAdapter;
+use kvs::{Address, KVStore, StoreConfig};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq)]
 enum SensorMode {
@@ -98,3 +99,182 @@ fn test_primitive_types() {
     assert_eq!(store.load_val::<u16, 3>(b"temp").unwrap(), temp);
     assert_eq!(store.load_val::<i32, 4>(b"charge").unwrap(), charge);
 }
+
+#[test]
+fn test_compressed_val() {
+    let mut store = tiny::create_store();
+    let mut scratch = [0; 64];
+    let mut packed = store.compressed(&mut scratch);
+    packed
+        .insert_val::<_, 64>(b"sensor", &[TEST_ENTRY.offset; 8])
+        .unwrap();
+    packed.insert_val::<_, 64>(b"config", &TEST_ENTRY).unwrap();
+
+    let offsets: [usize; 8] = packed.load_val::<_, 64>(b"sensor").unwrap();
+    assert_eq!(offsets, [TEST_ENTRY.offset; 8]);
+    let entry: SensorConfig = packed.load_val::<_, 64>(b"config").unwrap();
+    assert_eq!(entry, TEST_ENTRY);
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct Credentials<'a> {
+    ssid: &'a str,
+    #[serde(with = "serde_bytes_ref")]
+    psk: &'a [u8],
+}
+
+mod serde_bytes_ref {
+    pub fn serialize<S: serde::Serializer>(val: &[u8], ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_bytes(val)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(de: D) -> Result<&'de [u8], D::Error> {
+        serde::Deserialize::deserialize(de)
+    }
+}
+
+#[test]
+fn test_load_val_in() {
+    let mut store = tiny::create_store();
+    let creds = Credentials {
+        ssid: "home",
+        psk: b"correct-horse",
+    };
+    store.insert_val::<_, 64>(b"wifi", &creds).unwrap();
+
+    let mut buf = [0; 64];
+    let loaded: Credentials = store.load_val_in(b"wifi", &mut buf).unwrap();
+    assert_eq!(loaded, creds);
+
+    let mut buf = [0; 8];
+    let res = store.load_val_in::<Credentials>(b"wifi", &mut buf);
+    assert_eq!(res.unwrap_err(), kvs::Error::ValueOverflow);
+}
+
+#[test]
+fn test_load_val_stream() {
+    let mut store = tiny::create_store();
+    let samples = [TEST_ENTRY.offset as u32; 32];
+    store.insert_val::<_, 256>(b"samples", &samples).unwrap();
+    store.insert_val::<_, 64>(b"sensor", &TEST_ENTRY).unwrap();
+
+    let loaded: [u32; 32] = store.load_val_stream(b"samples", &mut []).unwrap();
+    assert_eq!(loaded, samples);
+    let entry: SensorConfig = store.load_val_stream(b"sensor", &mut []).unwrap();
+    assert_eq!(entry, TEST_ENTRY);
+
+    store.insert(b"short", &[0x80]).unwrap();
+    let res = store.load_val_stream::<u32>(b"short", &mut []);
+    assert!(matches!(res, Err(kvs::Error::SerializationError(_))));
+}
+
+#[derive(Debug, PartialEq)]
+struct Name {
+    buf: [u8; 16],
+    len: usize,
+}
+
+impl<'de> serde::Deserialize<'de> for Name {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl serde::de::Visitor<'_> for NameVisitor {
+            type Value = Name;
+
+            fn expecting(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+                fmt.write_str("a short string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, val: &str) -> Result<Name, E> {
+                let mut name = Name {
+                    buf: [0; 16],
+                    len: val.len(),
+                };
+                name.buf[..val.len()].copy_from_slice(val.as_bytes());
+                Ok(name)
+            }
+        }
+
+        de.deserialize_str(NameVisitor)
+    }
+}
+
+#[test]
+fn test_load_val_stream_scratch() {
+    let mut store = tiny::create_store();
+    store.insert_val::<_, 32>(b"name", &"sensor-1").unwrap();
+
+    let mut scratch = [0; 16];
+    let name: Name = store.load_val_stream(b"name", &mut scratch).unwrap();
+    assert_eq!(&name.buf[..name.len], b"sensor-1");
+
+    let res = store.load_val_stream::<Name>(b"name", &mut scratch[..4]);
+    assert!(matches!(res, Err(kvs::Error::SerializationError(_))));
+}
+
+#[test]
+fn test_insert_val_stream() {
+    let mut store = tiny::create_store();
+    let samples = ([u32::MAX; 32], [u32::MAX; 32]);
+    let bucket = store.insert_val_stream(b"samples", &samples).unwrap();
+    assert_eq!(bucket.val_len(), 64 * 5);
+    let loaded: ([u32; 32], [u32; 32]) = store.load_val_stream(b"samples", &mut []).unwrap();
+    assert_eq!(loaded, samples);
+
+    store.insert_val_stream(b"sensor", &TEST_ENTRY).unwrap();
+    let entry: SensorConfig = store.load_val::<_, 32>(b"sensor").unwrap();
+    assert_eq!(entry, TEST_ENTRY);
+
+    let creds = Credentials {
+        ssid: "home",
+        psk: b"correct-horse",
+    };
+    store.insert_val_stream(b"sensor", &creds).unwrap();
+    let mut buf = [0; 32];
+    let loaded: Credentials = store.load_val_in(b"sensor", &mut buf).unwrap();
+    assert_eq!(loaded, creds);
+}
+
+#[test]
+fn test_insert_val_stream_chunk_boundary() {
+    #[derive(Default)]
+    struct StrictAdapter(MemoryAdapter<{ tiny::STORE_SIZE }>);
+
+    impl StoreAdapter for StrictAdapter {
+        type Error = ();
+
+        fn read(&mut self, addr: Address, buf: &mut [u8]) -> Result<(), Self::Error> {
+            self.0.read(addr, buf)
+        }
+
+        fn write(&mut self, addr: Address, data: &[u8]) -> Result<(), Self::Error> {
+            if data.is_empty() {
+                return Err(());
+            }
+            self.0.write(addr, data)
+        }
+
+        fn max_address(&self) -> Address {
+            self.0.max_address()
+        }
+    }
+
+    let mut store = KVStore::<StrictAdapter, 32, 8>::open(
+        StrictAdapter::default(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+        true,
+    )
+    .unwrap();
+    let bucket = store.insert_val_stream(b"payload", &[7u8; 32]).unwrap();
+    assert_eq!(bucket.val_len(), 32);
+    let loaded: [u8; 32] = store.load_val::<_, 32>(b"payload").unwrap();
+    assert_eq!(loaded, [7; 32]);
+}
+
+#[test]
+fn test_insert_val_stream_overflow() {
+    let mut store = tiny::create_store();
+    let res = store.insert_val_stream(b"samples", &[u64::MAX; 128][..]);
+    assert_eq!(res.unwrap_err(), kvs::Error::StoreOverflow);
+    assert!(!store.exists(b"samples").unwrap());
+}
@@ -1,7 +1,10 @@
 use std::collections::HashSet;
 
+use byteorder::{BigEndian, LittleEndian};
+
 use kvs::adapters::ram::*;
-use kvs::{Grasshopper, KVStore, StoreConfig};
+use kvs::adapters::StoreAdapter;
+use kvs::{Fnv1a, Grasshopper, KVStore, SipHash, StoreConfig};
 
 const KEY_COLLISION_HASH: u16 = 58263;
 
@@ -27,11 +30,26 @@ const KEY_COLLISIONS: [&str; 16] = [
 #[test]
 fn test_collisions() {
     for key in KEY_COLLISIONS.iter() {
-        let hash = Grasshopper::<{ tiny::BUCKETS }>::new(tiny::MAX_HOPS, 0, key.as_bytes()).hash();
+        let hash = Grasshopper::new(tiny::BUCKETS, tiny::MAX_HOPS, 0, key.as_bytes()).hash();
         assert_eq!(KEY_COLLISION_HASH, hash);
     }
 }
 
+#[test]
+fn test_collisions_with_other_hashers() {
+    let tags: HashSet<u64> = KEY_COLLISIONS
+        .iter()
+        .map(|key| Grasshopper::with_hasher(SipHash, 32, 32, 0, key.as_bytes()).tag())
+        .collect();
+    assert_eq!(tags.len(), KEY_COLLISIONS.len());
+
+    let hashes: HashSet<u16> = KEY_COLLISIONS
+        .iter()
+        .map(|key| Grasshopper::with_hasher(Fnv1a, 32, 32, 0, key.as_bytes()).hash())
+        .collect();
+    assert!(hashes.len() > 1);
+}
+
 mod tiny {
     use crate::*;
 
@@ -203,6 +221,7 @@ fn test_load() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_exists() {
     let mut store = tiny::create_store();
     store.insert(b"foo", b"bar").unwrap();
@@ -363,14 +382,161 @@ fn test_patch_with_hole() {
     assert_eq!(err, kvs::Error::InvalidPatchOffset);
 }
 
+#[test]
+fn test_append_relocates() {
+    let mut store = tiny::create_store();
+    let first = store.insert(b"foo", b"bar").unwrap();
+    store.insert(b"bar", b"baz").unwrap();
+    let bytes_free = store.stats().unwrap().bytes_free;
+
+    let bucket = store.append(b"foo", b"baz").unwrap();
+    assert_ne!(bucket.val_address(), first.val_address());
+    assert_eq!(bucket.val_len(), 6);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"barbaz");
+    assert_eq!(store.load_slice(b"bar", &mut buf).unwrap(), b"baz");
+    assert_eq!(store.stats().unwrap().bytes_free, bytes_free - 3);
+
+    let bucket = store.patch(b"bar", 1, b"uzzard").unwrap();
+    assert_eq!(bucket.val_len(), 7);
+    assert_eq!(store.load_slice(b"bar", &mut buf).unwrap(), b"buzzard");
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"barbaz");
+}
+
+#[test]
+fn test_truncate() {
+    let mut store = tiny::create_store();
+    store.insert(b"foo", b"bar baz").unwrap();
+    let bytes_free = store.stats().unwrap().bytes_free;
+
+    let bucket = store.truncate(b"foo", 3).unwrap();
+    assert_eq!(bucket.val_len(), 3);
+    assert_eq!(store.stats().unwrap().bytes_free, bytes_free + 4);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
+
+    let err = store.truncate(b"foo", 4).unwrap_err();
+    assert_eq!(err, kvs::Error::InvalidValueLength);
+    let err = store.truncate(b"bar", 0).unwrap_err();
+    assert_eq!(err, kvs::Error::KeyNotFound);
+}
+
+#[test]
+fn test_resize() {
+    let mut store = tiny::create_store();
+    store.insert(b"foo", b"bar").unwrap();
+
+    let bucket = store.resize(b"foo", 6, b'!').unwrap();
+    assert_eq!(bucket.val_len(), 6);
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar!!!");
+
+    store.insert(b"bar", b"baz").unwrap();
+    let bucket = store.resize(b"foo", 10, b'?').unwrap();
+    assert_eq!(bucket.val_len(), 10);
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar!!!????");
+    assert_eq!(store.load_slice(b"bar", &mut buf).unwrap(), b"baz");
+
+    let bucket = store.resize(b"foo", 2, 0).unwrap();
+    assert_eq!(bucket.val_len(), 2);
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"ba");
+}
+
+#[test]
+fn test_rename() {
+    let mut store = tiny::create_store();
+    store.insert(b"foo", b"bar").unwrap();
+    store.insert(b"baz", b"qux").unwrap();
+
+    let old = store.lookup(b"foo").unwrap();
+    let bucket = store.rename(b"foo", b"fo").unwrap();
+    assert_eq!(bucket.val_address(), old.val_address());
+    assert!(!store.exists(b"foo").unwrap());
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"fo", &mut buf).unwrap(), b"bar");
+
+    let err = store.rename(b"fo", b"baz").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyExists);
+    let err = store.rename(b"foo", b"bar").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyNotFound);
+
+    store.rename(b"fo", b"foo").unwrap();
+    let bucket = store.rename(b"baz", b"long/baz").unwrap();
+    assert_eq!(bucket.key_len(), 8);
+    assert_eq!(store.load_slice(b"long/baz", &mut buf).unwrap(), b"qux");
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
+    assert_eq!(store.stats().unwrap().used_buckets, 2);
+}
+
+#[test]
+fn test_rename_into_free_space() {
+    let mut store = tiny::create_store();
+    store.insert(b"a", b"bcd").unwrap();
+    let old = store.insert(b"foo", &[0xaa; 200]).unwrap();
+    store.remove(b"a").unwrap();
+    let bytes_free = store.stats().unwrap().bytes_free;
+
+    let bucket = store.rename(b"foo", b"foobar").unwrap();
+    assert_eq!(bucket.val_address(), old.val_address());
+    assert_eq!(store.stats().unwrap().bytes_free, bytes_free - 3);
+
+    let mut buf = [0; 200];
+    assert_eq!(store.load_slice(b"foobar", &mut buf).unwrap(), [0xaa; 200]);
+}
+
+#[test]
+fn test_remove_prefix() {
+    let mut store = tiny::create_store();
+    let bytes_free = store.stats().unwrap().bytes_free;
+    for idx in 0..8u8 {
+        store
+            .insert(&[b'l', b'o', b'g', b'/', idx], b"foo")
+            .unwrap();
+    }
+    store.insert(b"log", b"bar").unwrap();
+    store.insert(b"sensor/1", b"baz").unwrap();
+
+    assert_eq!(store.remove_prefix(b"log/").unwrap(), 8);
+    assert_eq!(store.remove_prefix(b"log/").unwrap(), 0);
+    assert_eq!(store.keys().count(), 2);
+    assert!(store.exists(b"log").unwrap());
+
+    assert_eq!(store.remove_prefix(b"").unwrap(), 2);
+    assert_eq!(store.stats().unwrap().bytes_free, bytes_free);
+}
+
+#[test]
+fn test_erase_prefix() {
+    let mut store = tiny::create_store();
+    let bucket = store.insert(b"log/1", b"foo").unwrap();
+    store.insert(b"sensor/1", b"bar").unwrap();
+
+    assert_eq!(store.erase_prefix(b"log/", 0xaa).unwrap(), 1);
+    assert!(!store.exists(b"log/1").unwrap());
+
+    let mut buf = [0; 3];
+    store
+        .adapter()
+        .read(bucket.val_address(), &mut buf)
+        .unwrap();
+    assert_eq!(buf, [0xaa; 3]);
+}
+
 #[test]
 fn test_patch_no_space() {
     let mut store = tiny::create_store();
     store.insert(b"foo", b"bar").unwrap();
     store.insert(b"bar", b"baz").unwrap();
 
-    let err = store.append(b"foo", b"baz").unwrap_err();
-    assert_eq!(err, kvs::Error::ValueOverflow);
+    let data_size = store.stats().unwrap().data_size;
+    let err = store.append(b"foo", &vec![0; data_size]).unwrap_err();
+    assert_eq!(err, kvs::Error::StoreOverflow);
+
+    let mut buf = [0; 16];
+    assert_eq!(store.load_slice(b"foo", &mut buf).unwrap(), b"bar");
 }
 
 #[test]
@@ -388,6 +554,165 @@ fn test_patch_resize() {
     assert_eq!(&scratch[..bucket.val_len()], b"bar baz bar");
 }
 
+#[test]
+fn test_insert_if_absent() {
+    let mut store = tiny::create_store();
+    store.insert_if_absent(b"foo", b"bar").unwrap();
+
+    let err = store.insert_if_absent(b"foo", b"baz").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyExists);
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"foo", &mut scratch).unwrap();
+    assert_eq!(val, b"bar");
+}
+
+#[test]
+fn test_replace_if_exists() {
+    let mut store = tiny::create_store();
+
+    let err = store.replace_if_exists(b"foo", b"bar").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyNotFound);
+    assert!(!store.exists(b"foo").unwrap());
+
+    store.insert(b"foo", b"bar").unwrap();
+    store.replace_if_exists(b"foo", b"baz").unwrap();
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"foo", &mut scratch).unwrap();
+    assert_eq!(val, b"baz");
+}
+
+#[test]
+fn test_compare_and_swap() {
+    let mut store = tiny::create_store();
+
+    let err = store.compare_and_swap(b"foo", b"bar", b"baz").unwrap_err();
+    assert_eq!(err, kvs::Error::KeyNotFound);
+
+    store.insert(b"foo", b"bar").unwrap();
+
+    let err = store.compare_and_swap(b"foo", b"baz", b"qux").unwrap_err();
+    assert_eq!(err, kvs::Error::ValueMismatch);
+    let err = store.compare_and_swap(b"foo", b"ba", b"qux").unwrap_err();
+    assert_eq!(err, kvs::Error::ValueMismatch);
+
+    let bucket = store.compare_and_swap(b"foo", b"bar", b"quux").unwrap();
+    assert_eq!(bucket.val_len(), 4);
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"foo", &mut scratch).unwrap();
+    assert_eq!(val, b"quux");
+}
+
+#[test]
+fn test_numeric_values() {
+    let mut store = tiny::create_store();
+
+    store.set_u8(b"humidity", 225).unwrap();
+    store.set_u16::<BigEndian>(b"temp", 31_000).unwrap();
+    store.set_u32::<LittleEndian>(b"uptime", 100_500).unwrap();
+    store.set_i64::<BigEndian>(b"offset", -42).unwrap();
+
+    assert_eq!(store.get_u8(b"humidity").unwrap(), 225);
+    assert_eq!(store.get_u16::<BigEndian>(b"temp").unwrap(), 31_000);
+    assert_eq!(store.get_u32::<LittleEndian>(b"uptime").unwrap(), 100_500);
+    assert_eq!(store.get_i64::<BigEndian>(b"offset").unwrap(), -42);
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"uptime", &mut scratch).unwrap();
+    assert_eq!(val, &[0x94, 0x88, 0x01, 0x00]);
+
+    let err = store.get_u64::<BigEndian>(b"uptime").unwrap_err();
+    assert_eq!(err, kvs::Error::InvalidValueLength);
+}
+
+#[test]
+fn test_numeric_update_in_place() {
+    let mut store = tiny::create_store();
+
+    let bucket = store.set_u32::<BigEndian>(b"counter", 1).unwrap();
+    let address = bucket.val_address();
+    let bucket = store.set_u32::<BigEndian>(b"counter", 2).unwrap();
+    assert_eq!(bucket.val_address(), address);
+    assert_eq!(store.get_u32::<BigEndian>(b"counter").unwrap(), 2);
+}
+
+#[test]
+fn test_increment() {
+    let mut store = tiny::create_store();
+
+    assert_eq!(store.increment::<BigEndian>(b"boot", 1).unwrap(), 1);
+    assert_eq!(store.increment::<BigEndian>(b"boot", 1).unwrap(), 2);
+    assert_eq!(store.increment::<BigEndian>(b"boot", -5).unwrap(), -3);
+    assert_eq!(store.get_i64::<BigEndian>(b"boot").unwrap(), -3);
+
+    store.set_u8(b"errors", 0xfe).unwrap();
+    assert_eq!(store.increment::<BigEndian>(b"errors", 1).unwrap(), 0xff);
+    assert_eq!(store.increment::<BigEndian>(b"errors", 1).unwrap(), 0);
+    assert_eq!(store.increment::<BigEndian>(b"errors", -1).unwrap(), 0xff);
+
+    store
+        .set_u32::<BigEndian>(b"uptime", i32::MAX as u32)
+        .unwrap();
+    let val = store.increment::<BigEndian>(b"uptime", 1).unwrap();
+    assert_eq!(val, 1 << 31);
+    assert_eq!(store.get_u32::<BigEndian>(b"uptime").unwrap(), 1 << 31);
+
+    store.set_i32::<LittleEndian>(b"seq", 41).unwrap();
+    assert_eq!(store.increment::<LittleEndian>(b"seq", 1).unwrap(), 42);
+    assert_eq!(store.get_i32::<LittleEndian>(b"seq").unwrap(), 42);
+
+    store.insert(b"name", b"foo").unwrap();
+    let err = store.increment::<BigEndian>(b"name", 1).unwrap_err();
+    assert_eq!(err, kvs::Error::InvalidValueLength);
+}
+
+#[test]
+fn test_stats() {
+    let mut store = tiny::create_store();
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.buckets, tiny::BUCKETS);
+    assert_eq!(stats.used_buckets, 0);
+    assert_eq!(stats.max_probe_distance, 0);
+    assert_eq!(stats.data_size, 752);
+    assert_eq!(stats.bytes_used, 0);
+    assert_eq!(stats.bytes_free, 752);
+    assert_eq!(stats.largest_free, 752);
+    assert_eq!(stats.slots, tiny::SLOTS);
+    assert_eq!(stats.used_slots, 1);
+    assert_eq!(stats.load_factor(), 0.0);
+    assert_eq!(stats.fragmentation(), 0.0);
+
+    store.insert(b"foo", b"bar").unwrap();
+    store.insert(b"bar", b"baz").unwrap();
+    store.remove(b"foo").unwrap();
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.used_buckets, 1);
+    assert_eq!(stats.max_probe_distance, 1);
+    assert_eq!(stats.bytes_used, 6);
+    assert_eq!(stats.bytes_free, 746);
+    assert_eq!(stats.largest_free, 740);
+    assert_eq!(stats.used_slots, 2);
+    assert_eq!(stats.load_factor(), 1.0 / 32.0);
+    assert!(stats.fragmentation() > 0.0);
+}
+
+#[test]
+fn test_stats_probe_distance() {
+    let mut store = tiny::create_store();
+    for key in KEY_COLLISIONS.iter() {
+        store.insert(key.as_bytes(), b"foo").unwrap();
+    }
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.used_buckets, KEY_COLLISIONS.len());
+    assert_eq!(stats.load_factor(), 0.5);
+    assert!(stats.max_probe_distance > 1);
+}
+
 #[test]
 fn test_reset() {
     let mut store = tiny::create_store();
@@ -438,6 +763,42 @@ fn test_erase() {
     assert_eq!(&scratch[..bucket.val_len()], b"bar");
 }
 
+#[test]
+fn test_empty_key() {
+    let mut store = tiny::create_store();
+
+    let err = store.insert(b"", b"bar").unwrap_err();
+    assert_eq!(err, kvs::Error::InvalidKey);
+    assert!(!store.exists(b"").unwrap());
+}
+
+#[test]
+fn test_hop_limit() {
+    let mut store = tiny::Store::open(
+        MemoryAdapter::default(),
+        StoreConfig::new(tiny::MAGIC, 2),
+        true,
+    )
+    .unwrap();
+
+    let mut inserted = 0;
+    for idx in 0..tiny::BUCKETS as u8 {
+        match store.insert(&[b'k', idx], b"v") {
+            Ok(_) => inserted += 1,
+            Err(kvs::Error::IndexOverflow) => {}
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+    assert!(inserted < tiny::BUCKETS);
+
+    for idx in 0..tiny::BUCKETS as u8 {
+        if store.exists(&[b'k', idx]).unwrap() {
+            inserted -= 1;
+        }
+    }
+    assert_eq!(inserted, 0);
+}
+
 #[test]
 fn test_hash_collision() {
     let mut store = tiny::create_store();
@@ -497,11 +858,19 @@ fn test_hash_collisions() {
 fn test_compatibility() {
     let data = include_bytes!("./tiny.db");
 
-    let mut store = tiny::Store::open(
+    let err = tiny::Store::open(
         MemoryAdapter::new(*data),
         StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(34),
         false,
     )
+    .err()
+    .unwrap();
+    assert_eq!(err, kvs::Error::LegacyFormat);
+
+    let mut store = tiny::Store::upgrade(
+        MemoryAdapter::new(*data),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(34),
+    )
     .unwrap();
 
     let mut scratch = [0; 64];
@@ -510,4 +879,207 @@ fn test_compatibility() {
         assert_eq!(bucket.val_len(), key.len());
         assert_eq!(&scratch[..bucket.val_len()], key.as_bytes());
     }
+
+    let adapter = store.close();
+    let mut store = tiny::Store::open(
+        adapter,
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(34),
+        false,
+    )
+    .unwrap();
+
+    for key in KEY_COLLISIONS.iter() {
+        let bucket = store.load(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(&scratch[..bucket.val_len()], key.as_bytes());
+    }
+}
+
+#[test]
+fn test_rehash_enables_compression() {
+    let data = include_bytes!("./tiny.db");
+    let cfg = || StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(34);
+    let mut store = tiny::Store::upgrade(MemoryAdapter::new(*data), cfg()).unwrap();
+
+    let mut scratch = [0; 64];
+    let err = store
+        .compressed(&mut scratch)
+        .insert(b"cfg", b"foo")
+        .unwrap_err();
+    assert_eq!(err, kvs::Error::LegacyFormat);
+
+    for key in KEY_COLLISIONS.iter().skip(8) {
+        store.remove(key.as_bytes()).unwrap();
+    }
+    let mut store = tiny::Store::rehash(store.close(), cfg()).unwrap();
+    store
+        .compressed(&mut scratch)
+        .insert(b"cfg", b"foo")
+        .unwrap();
+    for key in KEY_COLLISIONS.iter().take(8) {
+        let bucket = store.load(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(&scratch[..bucket.val_len()], key.as_bytes());
+        assert!(!bucket.is_compressed());
+    }
+
+    let mut store = tiny::Store::open(store.close(), cfg(), false).unwrap();
+    let mut buf = [0; 8];
+    let val = store.compressed(&mut scratch).load_slice(b"cfg", &mut buf);
+    assert_eq!(val.unwrap(), b"foo");
+}
+
+#[test]
+fn test_upgrade_current_format() {
+    let mut store = tiny::create_store();
+    store.insert(b"foo", b"bar").unwrap();
+
+    let mut store =
+        tiny::Store::upgrade(store.close(), StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS)).unwrap();
+
+    let mut scratch = [0; 16];
+    let val = store.load_slice(b"foo", &mut scratch).unwrap();
+    assert_eq!(val, b"bar");
+}
+
+#[test]
+fn test_reopen_store_with_unsupported_version() {
+    let mut adapter = tiny::create_store().close();
+    adapter.memory[10] = kvs::FORMAT_VERSION + 1;
+
+    let store = tiny::Store::open(adapter, StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS), true);
+    assert!(store.is_err());
+    if let Err(err) = store {
+        assert_eq!(err, kvs::Error::UnsupportedVersion);
+    }
+}
+
+mod rehash {
+    use crate::*;
+
+    pub const STORE_SIZE: usize = 2048;
+
+    pub type Store<const BUCKETS: usize> = KVStore<MemoryAdapter<STORE_SIZE>, BUCKETS, 8>;
+
+    pub fn create_store() -> Store<32> {
+        let mut store = Store::<32>::open(
+            MemoryAdapter::default(),
+            StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS),
+            true,
+        )
+        .unwrap();
+
+        for key in KEY_COLLISIONS.iter().take(12) {
+            store.insert(key.as_bytes(), key.as_bytes()).unwrap();
+        }
+        store
+    }
+}
+
+#[test]
+fn test_rehash() {
+    let store = rehash::create_store();
+    let mut store =
+        rehash::Store::<64>::rehash(store.close(), StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS))
+            .unwrap();
+
+    let mut scratch = [0; 64];
+    for key in KEY_COLLISIONS.iter().take(12) {
+        let val = store.load_slice(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(val, key.as_bytes());
+    }
+    store.insert(b"foo", b"bar").unwrap();
+
+    let mut store = rehash::Store::<16>::rehash(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(42),
+    )
+    .unwrap();
+    store.insert(b"bar", b"baz").unwrap();
+
+    for key in KEY_COLLISIONS.iter().take(12) {
+        let val = store.load_slice(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(val, key.as_bytes());
+    }
+    let val = store.load_slice(b"foo", &mut scratch).unwrap();
+    assert_eq!(val, b"bar");
+
+    let err = rehash::Store::<32>::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(42),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err, kvs::Error::InvalidCapacity);
+}
+
+#[test]
+fn test_rehash_reopen() {
+    let store = rehash::create_store();
+    let store = rehash::Store::<16>::rehash(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(42),
+    )
+    .unwrap();
+    let mut store = rehash::Store::<16>::open(
+        store.close(),
+        StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS).nonce(42),
+        false,
+    )
+    .unwrap();
+
+    let mut scratch = [0; 64];
+    for key in KEY_COLLISIONS.iter().take(12) {
+        let val = store.load_slice(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(val, key.as_bytes());
+    }
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.buckets, 16);
+    assert_eq!(stats.used_buckets, 12);
+    assert_eq!(stats.data_size, rehash::STORE_SIZE - 16 - 8 * 16);
+}
+
+#[test]
+fn test_rehash_index_overflow() {
+    let store = rehash::create_store();
+    let err =
+        rehash::Store::<8>::rehash(store.close(), StoreConfig::new(tiny::MAGIC, tiny::MAX_HOPS))
+            .err()
+            .unwrap();
+    assert_eq!(err, kvs::Error::IndexOverflow);
+}
+
+#[test]
+fn test_failed_rehash_keeps_data() {
+    type Store<A, const BUCKETS: usize> = KVStore<A, BUCKETS, 8>;
+
+    let cfg = || StoreConfig::new(tiny::MAGIC, 4);
+    let mut store =
+        Store::<MemoryAdapter<4096>, 64>::open(MemoryAdapter::default(), cfg(), true).unwrap();
+    let mut keys = Vec::new();
+    for idx in 0.. {
+        let key = format!("key{idx}");
+        match store.insert(key.as_bytes(), key.as_bytes()) {
+            Ok(_) => keys.push(key),
+            Err(err) => assert_eq!(err, kvs::Error::IndexOverflow),
+        }
+        if keys.len() == 39 {
+            break;
+        }
+    }
+    let mut memory = store.close().release();
+
+    let err = Store::<RefMemoryAdapter, 40>::rehash(RefMemoryAdapter::new(&mut memory), cfg())
+        .err()
+        .unwrap();
+    assert_eq!(err, kvs::Error::IndexOverflow);
+
+    let mut store =
+        Store::<MemoryAdapter<4096>, 64>::open(MemoryAdapter::new(memory), cfg(), false).unwrap();
+    let mut scratch = [0; 16];
+    for key in keys.iter() {
+        let val = store.load_slice(key.as_bytes(), &mut scratch).unwrap();
+        assert_eq!(val, key.as_bytes());
+    }
+    assert_eq!(store.stats().unwrap().used_buckets, 39);
 }